@@ -1,20 +1,116 @@
 extern crate std;
 
-use check_timed_logs_fast::Config;
+use check_timed_logs_fast::{read_logfile_list, Config, ConfigError, Encoding, LineFormat, Locale, Rotation, State};
+use std::str::FromStr;
 
 fn print_usage(program: &str) {
   let brief = format!("Usage: {}
     -pattern <regex-pattern>
-    -logfile <path to log file>
+    [-format <plain|json|logfmt|docker|cri|syslog|journal|access>] [-timefield <field>] [-matchfield <field>] [-filter <field=value|field~regex>]
+    [-severity <emerg|alert|crit|err|warning|notice|info|debug>]
+    [-errorstatus <5xx,4xx,...>] [-errorratewarning <percent>] [-errorratecritical <percent>]
+    [-latencywarning <ms>] [-latencycritical <ms>]
+    [-burstminutes <minutes>] [-burstwarning <matches>] [-burstcritical <matches>]
+    [-ratewarning <matches per minute>] [-ratecritical <matches per minute>]
+    [-trendwarning <matches|percent%>] [-trendcritical <matches|percent%>]
+    [-distinct <group name>] [-distinctapprox] [-distinctwarning <values>] [-distinctcritical <values>]
+    -logfile <path to log file> [-logfile <path to further log file> ...]
+    [-logfilelist <file with one log file path or glob per line>]
+    [-logdir <directory> [-include <glob>] [-maxdepth <levels>]]
     -interval <minutes>
     [-timepattern <POSIX time pattern>]
-    [-warning|w <number_of_required_hits>] [-critical|c <number_of_required_hits>]
+    [-warning|w <number_of_required_hits>[%]] [-critical|c <number_of_required_hits>[%]]
     [-timeposition <time_string_index_on_line>]
+    [-fallbacktimepattern <POSIX time pattern>] [-fallbacktimeposition <time_string_index_on_line>]
+    [-locale <en|de|fr|es|it|nl>]
+    [-encoding <utf-8|utf-8-lossy|latin1|windows-1252|utf-16le>]
+    [-skew <seconds>]
+    [-multiline]
+    [-futuretolerance <seconds>] [-excludefuture] [-futurestate <ok|warning|critical|unknown>]
+    [-rotation <any,exact,numeric,dateext,old>] [-rotationregex <regex-pattern>]
+    [-exclude <glob>]
+    [-activefileage <seconds>] [-truncatedstate <ok|warning|critical|unknown>]
+    [-ioerrorstate <ignore|warning|critical|unknown>]
+    [-stalewarning <minutes>] [-stalecritical <minutes>]
 
-    To allow for rotating logfiles, any file that matches the passed filename and
-    was changed within the passed interval is checked. e.g. If you pass /var/log/applog,
-    this could match /var/log/applog.0, /var/log/applog.old and so on. However, it does
-    not handle compressed (e.g. gzip/bzip) files.
+    -logfile can be passed multiple times, the matches in all files are added up.
+    With -logfilelist the log files are read from a file instead, empty lines and
+    lines starting with # are skipped. Both can be combined.
+
+    -logdir searches all files below a directory, it can be used instead of or in
+    addition to -logfile and passed multiple times. Only files whose name or path
+    relative to the directory match one of the -include globs are searched (all if
+    none is given), -exclude applies as well. -maxdepth 1 only searches the files
+    directly in the directory. The matches per directory are listed below the
+    status line.
+
+    To allow for rotating logfiles, the rotated versions of the passed filename which
+    were changed within the passed interval are checked. e.g. If you pass /var/log/applog,
+    this matches /var/log/applog.0, /var/log/applog-20121231, /var/log/applog.old and
+    so on, but not /var/log/applog-audit. However, it does
+    not handle compressed (e.g. gzip/bzip) files. The files are searched from the most
+    recently modified one, the search stops at the first file which contains lines
+    older than the interval.
+
+    Use -rotation to choose the naming schemes of your rotated files (comma
+    separated): exact (only the file itself), numeric (applog.1), dateext
+    (applog-20121231) and old (applog.old), which are the default, or any (every
+    file starting with the passed filename, like earlier versions).
+    -rotationregex additionally searches all files in the directory whose name
+    matches the regex. Files whose path or name match an -exclude glob are never
+    searched, -exclude can be passed multiple times.
+
+    The passed log files and rotated files modified within the last -activefileage
+    seconds (default 60) are read in chunks instead of being mapped to memory,
+    because they may be truncated during the search (e.g. by logrotate's
+    copytruncate). If a file was truncated, the
+    -truncatedstate (default warning) is raised since matches may be missing.
+    Files which can not be read (e.g. missing permissions) are skipped and listed in
+    the output, -ioerrorstate (default unknown) is raised. Use ignore to disable this.
+
+    A crashed application doesn't write errors either. With -stalewarning and
+    -stalecritical the state is raised if the most recently modified file or the
+    newest timestamp on a line is older than the given number of minutes.
+
+    With -format json each line is a json object, nested fields are named
+    outer.inner. The timestamp is taken from -timefield (default: the first of ts,
+    time, timestamp, @timestamp), it can be seconds or milliseconds since 1970, an
+    RFC 3339 date or a date in one of the time patterns. The pattern is matched
+    against the -matchfield fields (can be passed multiple times, default the whole
+    line). -filter level=error requires a field to have a value, -filter msg~regex
+    to match a regex, all filters need to match. -pattern may be left out if
+    -filter is given. Lines which are no json objects are skipped.
+    -format logfmt works the same for lines of key=value pairs, e.g.
+    ts=2018-09-13T00:03:01Z level=error msg=\"db timeout\".
+
+    Container logs: -format docker reads the json-file logs of docker
+    (/var/lib/docker/containers/*/*-json.log), -format cri the logs of kubernetes
+    (/var/log/pods/**/*.log). The pattern is matched against the line written by
+    the application, lines which were split into several partial lines are joined
+    first. The fields time, stream and log can be used with -filter.
+
+    -format syslog reads RFC 5424 and RFC 3164 lines (e.g. /var/log/messages) with
+    the fields time, host, app, pid, msgid, message and, if the line starts with the
+    priority, facility and severity. -severity err only matches lines with severity
+    err or worse, lines without priority never match then. Example for errors of
+    sshd: -format syslog -filter app=sshd -severity err.
+
+    -format journal reads the output of `journalctl -o export` or `journalctl -o json`
+    from a file or, with -logfile -, from stdin. The timestamp is taken from
+    __REALTIME_TIMESTAMP and the pattern is matched against MESSAGE. Use e.g.
+    -filter _SYSTEMD_UNIT=nginx.service, -severity applies to PRIORITY.
+
+    -format access reads apache's and nginx' common and combined log format with
+    the fields host, user, time, method, path, protocol, status, bytes, referer,
+    user_agent and response_time (a number at the end of the line, in seconds if
+    it has a decimal point as nginx' $request_time, otherwise in microseconds as
+    apache's %D). The requests in the interval which pass the -filter are counted:
+    -errorratewarning and -errorratecritical are the percentages of requests with a
+    -errorstatus (default 5xx) above which the state is raised, -latencywarning and
+    -latencycritical the average response times in milliseconds. -pattern may be
+    left out if one of these is given, the matches then don't raise a state. E.g.
+    -format access -errorratecritical 5 is critical if more than 5% of the requests
+    failed with 5xx.
 
     Default time pattern is: %Y-%m-%d %H:%M:%S  => 2012-12-31 17:20:40
     Example Time patterns (from a RHEL system):
@@ -23,17 +119,68 @@ fn print_usage(program: &str) {
       Websphere Logs: %d-%b-%Y %I:%M:%S %p => 31-Dec-2012 05:20:40 PM
       Nagios logs: %s => 1361260238 (seconds since 01-01-1970)
 
+    Month and weekday names (%b, %a, ...) are parsed in english. Use -locale to
+    parse localized names instead, e.g. -locale de for `13. Okt 2018 00:03:01`.
+
+    Default encoding is utf-8, the search in a file stops at the first line which is
+    not valid utf-8. With utf-8-lossy only the invalid characters of that line are
+    replaced, latin1, windows-1252 and utf-16le decode the whole file.
+
     For a posix time format documentation check out:
     http://linux.die.net/man/3/strftime
 
     Default warning/critical threshold of pattern matches to find is: 1 -> unless you
     change this, you will only get OK or CRITICAL, but never WARNING.
+    With a % sign (e.g. -warning 1% -critical 5%) the threshold is the percentage of
+    matches among all lines with a timestamp in the interval, so it scales with the
+    traffic. The number of matches, of lines and the percentage are added to the
+    output and to the performance data.
+
+    Bursts: -burstwarning and -burstcritical raise the state if there are more
+    matches within any -burstminutes (default 1) consecutive minutes of the
+    interval, e.g. -interval 15 -burstcritical 20 for more than 20 matches within
+    one minute. -ratewarning and -ratecritical raise it if the average number of
+    matches per minute of the interval is higher. With -verbose the matches per
+    minute are listed below the status line.
+
+    Trends: with -trendwarning or -trendcritical the interval before the current
+    one is searched as well. The state is raised if the matches grew by more than
+    the given number (e.g. 10) or percentage (e.g. 50%) compared to it. For the
+    percentage an empty previous interval counts as one match.
+
+    Distinct values: -distinct counts the different values of a named group of the
+    pattern among the matches, e.g. the source addresses of failed logins with
+    -pattern 'Failed password for .* from (?P<ip>\\S+)' -distinct ip. The state is
+    raised by -distinctwarning and -distinctcritical if at least as many values were
+    found; without -warning and -critical the number of matches raises no state.
+    -distinctapprox estimates the count (about 1% error) with constant memory
+    instead of keeping all values, for very large logs.
 
     Default time position is 0
     Time Position: each line is split into an array of strings on the space character,
     this provides the index for the first time string.
     Note: If the line starts with the time, that means we start at index 0.
 
+    Fallback time patterns: -fallbacktimepattern can be passed multiple times for
+    files which mix several date formats. The patterns are tried in order if the
+    -timepattern can not be parsed from a line. The n-th -fallbacktimeposition
+    belongs to the n-th fallback pattern, the default position is 0.
+
+    Lines are searched from the end of the file and the search stops at the first
+    line older than the interval. If the application writes lines slightly out of
+    order, -skew allows lines to be up to <seconds> older than the interval before
+    the search stops. These lines are not counted. Default is 0.
+
+    Multi-line entries (e.g. stack traces): with -multiline all lines without a
+    timestamp are appended to the preceding line which has one. The pattern is
+    then matched against the whole entry, lines are joined with `\\n`.
+
+    Lines with a timestamp more than -futuretolerance seconds (default 60) ahead of
+    the current time are counted as lines from the future. With -excludefuture they
+    are never matched. If they make up more than half of the recent lines of a file,
+    the timezone is likely misconfigured and -futurestate (default warning) is
+    raised. Use -futurestate ok to disable this.
+
     The values for interval and warning/critical need to be larger than zero.", program);
   println!("{}", &brief);
 }
@@ -43,17 +190,75 @@ fn print_version() {
   println!("{}", VERSION);
 }
 
+/// Parses the number of matches of -warning or -critical. A percentage of
+/// the lines in the interval (e.g. `5%`) replaces the number.
+fn parse_threshold(flag: &str, arg: &str) -> (u64, Option<f64>) {
+  let exit = |e: &dyn std::fmt::Debug| -> ! {
+    println!("ERROR: \"{} {}\" can not be parsed due to {:?}", flag, arg, e);
+    std::process::exit(3);
+  };
+
+  match arg.strip_suffix('%') {
+    Some(percentage) => (u64::MAX, Some(percentage.parse().unwrap_or_else(|e| exit(&e)))),
+    None => (arg.parse().unwrap_or_else(|e| exit(&e)), None),
+  }
+}
+
 // the selfmade parsing is necessary because the original plugin uses `-`
 // instead of `--` for the flags. the getopts crate only supports `--` though.
 pub fn parse() -> Result<Config, String> {
   let mut interval_to_check: u64 = 0;
   let mut search_pattern: String = String::from("");
-  let mut logfile: String = String::from("");
+  let mut logfiles: Vec<String> = Vec::new();
+  let mut logfile_lists: Vec<String> = Vec::new();
+  let mut logdirs: Vec<String> = Vec::new();
+  let mut include: Vec<String> = Vec::new();
+  let mut max_depth = None;
 
   let mut max_critical_matches = 1;
   let mut max_warning_matches = 1;
+  let mut warning_percentage = None;
+  let mut critical_percentage = None;
   let mut date_pattern = String::from("%b %d %H:%M:%S");
   let mut timeposition = 0;
+  let mut fallback_patterns: Vec<String> = Vec::new();
+  let mut fallback_positions: Vec<usize> = Vec::new();
+  let mut locale = Locale::En;
+  let mut encoding = Encoding::Utf8;
+  let mut skew_tolerance = 0;
+  let mut multiline = false;
+  let mut future_tolerance = 60;
+  let mut exclude_future = false;
+  let mut future_state = State::Warning;
+  let mut rotations: Vec<Rotation> = Vec::new();
+  let mut exclude: Vec<String> = Vec::new();
+  let mut active_file_age = 60;
+  let mut truncated_state = State::Warning;
+  let mut io_error_state = State::Unknown;
+  let mut stale_warning = None;
+  let mut stale_critical = None;
+  let mut line_format = LineFormat::Plain;
+  let mut time_field = None;
+  let mut match_fields: Vec<String> = Vec::new();
+  let mut filters: Vec<String> = Vec::new();
+  let mut max_severity = None;
+  let mut error_classes = None;
+  let mut error_rate_warning = None;
+  let mut error_rate_critical = None;
+  let mut latency_warning = None;
+  let mut latency_critical = None;
+  let mut burst_minutes = 1;
+  let mut burst_warning = None;
+  let mut burst_critical = None;
+  let mut rate_warning = None;
+  let mut rate_critical = None;
+  let mut trend_warning = None;
+  let mut trend_critical = None;
+  let mut distinct_group = None;
+  let mut distinct_approximate = false;
+  let mut distinct_warning = None;
+  let mut distinct_critical = None;
+  let mut match_thresholds_given = false;
   let mut debug = false;
   let mut verbose = false;
 
@@ -85,12 +290,36 @@ pub fn parse() -> Result<Config, String> {
       "-v" | "-verbose" => {
         verbose = true;
       },
+      "-multiline" => {
+        multiline = true;
+      },
+      "-excludefuture" => {
+        exclude_future = true;
+      },
+      "-distinctapprox" => {
+        distinct_approximate = true;
+      },
       &_ => {
         // if the current argument can not be matched
         // let's look if it is a value for a preceding flag
         match prior_arg {
           "-l" | "-logfile" => {
-            logfile = arg.clone().to_string();
+            logfiles.push(arg.to_string());
+          },
+          "-logfilelist" => {
+            logfile_lists.push(arg.to_string());
+          },
+          "-logdir" => {
+            logdirs.push(arg.to_string());
+          },
+          "-include" => {
+            include.push(arg.to_string());
+          },
+          "-maxdepth" => {
+            max_depth = Some(arg.parse().unwrap_or_else(|e| {
+              println!("ERROR: \"-maxdepth {}\" can not be parsed due to {:?}", arg, e);
+              std::process::exit(3);
+            }));
           },
           "-p" | "-pattern" => {
             search_pattern = arg.clone().to_string();
@@ -103,16 +332,16 @@ pub fn parse() -> Result<Config, String> {
           },
         
           "-w" | "-warning" => {
-            max_warning_matches = arg.parse().unwrap_or_else(|e| {
-              println!("ERROR: \"-warning {}\" can not be parsed due to {:?}", arg, e);
-              std::process::exit(3);
-            });
+            match_thresholds_given = true;
+            let (matches, percentage) = parse_threshold("-warning", arg);
+            max_warning_matches = matches;
+            warning_percentage = percentage;
           },
           "-c" | "-critical" => {
-            max_critical_matches = arg.parse().unwrap_or_else(|e| {
-              println!("ERROR: \"-critical {}\" can not be parsed due to {:?}", arg, e);
-              std::process::exit(3);
-            });
+            match_thresholds_given = true;
+            let (matches, percentage) = parse_threshold("-critical", arg);
+            max_critical_matches = matches;
+            critical_percentage = percentage;
           },
           "-timepattern" => {
             date_pattern = arg.clone().to_string();
@@ -123,6 +352,193 @@ pub fn parse() -> Result<Config, String> {
               std::process::exit(3);
             });
           },
+          "-fallbacktimepattern" => {
+            fallback_patterns.push(arg.to_string());
+          },
+          "-fallbacktimeposition" => {
+            fallback_positions.push(arg.parse().unwrap_or_else(|e| {
+              println!("ERROR: \"-fallbacktimeposition {}\" can not be parsed due to {:?}", arg, e);
+              std::process::exit(3);
+            }));
+          },
+          "-locale" => {
+            locale = arg.parse().unwrap_or_else(|e| {
+              println!("ERROR: \"-locale {}\" can not be parsed due to {:?}", arg, e);
+              std::process::exit(3);
+            });
+          },
+          "-encoding" => {
+            encoding = arg.parse().unwrap_or_else(|e| {
+              println!("ERROR: \"-encoding {}\" can not be parsed due to {:?}", arg, e);
+              std::process::exit(3);
+            });
+          },
+          "-skew" => {
+            skew_tolerance = arg.parse().unwrap_or_else(|e| {
+              println!("ERROR: \"-skew {}\" can not be parsed due to {:?}", arg, e);
+              std::process::exit(3);
+            });
+          },
+          "-futuretolerance" => {
+            future_tolerance = arg.parse().unwrap_or_else(|e| {
+              println!("ERROR: \"-futuretolerance {}\" can not be parsed due to {:?}", arg, e);
+              std::process::exit(3);
+            });
+          },
+          "-futurestate" => {
+            future_state = arg.parse().unwrap_or_else(|e| {
+              println!("ERROR: \"-futurestate {}\" can not be parsed due to {:?}", arg, e);
+              std::process::exit(3);
+            });
+          },
+          "-rotation" => {
+            let parsed: Result<Vec<Rotation>, ConfigError> = arg.split(',').map(Rotation::from_str).collect();
+            rotations.extend(parsed.unwrap_or_else(|e| {
+              println!("ERROR: \"-rotation {}\" can not be parsed due to {:?}", arg, e);
+              std::process::exit(3);
+            }));
+          },
+          "-rotationregex" => {
+            rotations.push(Rotation::from_regex(arg).unwrap_or_else(|e| {
+              println!("ERROR: \"-rotationregex {}\" can not be parsed due to {:?}", arg, e);
+              std::process::exit(3);
+            }));
+          },
+          "-exclude" => {
+            exclude.push(arg.to_string());
+          },
+          "-activefileage" => {
+            active_file_age = arg.parse().unwrap_or_else(|e| {
+              println!("ERROR: \"-activefileage {}\" can not be parsed due to {:?}", arg, e);
+              std::process::exit(3);
+            });
+          },
+          "-truncatedstate" => {
+            truncated_state = arg.parse().unwrap_or_else(|e| {
+              println!("ERROR: \"-truncatedstate {}\" can not be parsed due to {:?}", arg, e);
+              std::process::exit(3);
+            });
+          },
+          "-ioerrorstate" => {
+            io_error_state = arg.parse().unwrap_or_else(|e| {
+              println!("ERROR: \"-ioerrorstate {}\" can not be parsed due to {:?}", arg, e);
+              std::process::exit(3);
+            });
+          },
+          "-stalewarning" => {
+            stale_warning = Some(arg.parse().unwrap_or_else(|e| {
+              println!("ERROR: \"-stalewarning {}\" can not be parsed due to {:?}", arg, e);
+              std::process::exit(3);
+            }));
+          },
+          "-stalecritical" => {
+            stale_critical = Some(arg.parse().unwrap_or_else(|e| {
+              println!("ERROR: \"-stalecritical {}\" can not be parsed due to {:?}", arg, e);
+              std::process::exit(3);
+            }));
+          },
+          "-format" => {
+            line_format = arg.parse().unwrap_or_else(|e| {
+              println!("ERROR: \"-format {}\" can not be parsed due to {:?}", arg, e);
+              std::process::exit(3);
+            });
+          },
+          "-timefield" => {
+            time_field = Some(arg.to_string());
+          },
+          "-matchfield" => {
+            match_fields.push(arg.to_string());
+          },
+          "-filter" => {
+            filters.push(arg.to_string());
+          },
+          "-severity" => {
+            max_severity = Some(arg.to_string());
+          },
+          "-errorstatus" => {
+            error_classes = Some(arg.to_string());
+          },
+          "-errorratewarning" => {
+            error_rate_warning = Some(arg.parse().unwrap_or_else(|e| {
+              println!("ERROR: \"-errorratewarning {}\" can not be parsed due to {:?}", arg, e);
+              std::process::exit(3);
+            }));
+          },
+          "-errorratecritical" => {
+            error_rate_critical = Some(arg.parse().unwrap_or_else(|e| {
+              println!("ERROR: \"-errorratecritical {}\" can not be parsed due to {:?}", arg, e);
+              std::process::exit(3);
+            }));
+          },
+          "-latencywarning" => {
+            latency_warning = Some(arg.parse().unwrap_or_else(|e| {
+              println!("ERROR: \"-latencywarning {}\" can not be parsed due to {:?}", arg, e);
+              std::process::exit(3);
+            }));
+          },
+          "-burstminutes" => {
+            burst_minutes = arg.parse().unwrap_or_else(|e| {
+              println!("ERROR: \"-burstminutes {}\" can not be parsed due to {:?}", arg, e);
+              std::process::exit(3);
+            });
+          },
+          "-burstwarning" => {
+            burst_warning = Some(arg.parse().unwrap_or_else(|e| {
+              println!("ERROR: \"-burstwarning {}\" can not be parsed due to {:?}", arg, e);
+              std::process::exit(3);
+            }));
+          },
+          "-burstcritical" => {
+            burst_critical = Some(arg.parse().unwrap_or_else(|e| {
+              println!("ERROR: \"-burstcritical {}\" can not be parsed due to {:?}", arg, e);
+              std::process::exit(3);
+            }));
+          },
+          "-ratewarning" => {
+            rate_warning = Some(arg.parse().unwrap_or_else(|e| {
+              println!("ERROR: \"-ratewarning {}\" can not be parsed due to {:?}", arg, e);
+              std::process::exit(3);
+            }));
+          },
+          "-ratecritical" => {
+            rate_critical = Some(arg.parse().unwrap_or_else(|e| {
+              println!("ERROR: \"-ratecritical {}\" can not be parsed due to {:?}", arg, e);
+              std::process::exit(3);
+            }));
+          },
+          "-trendwarning" => {
+            trend_warning = Some(arg.parse().unwrap_or_else(|e: ConfigError| {
+              println!("ERROR: \"-trendwarning {}\" can not be parsed: {}", arg, String::from(e));
+              std::process::exit(3);
+            }));
+          },
+          "-trendcritical" => {
+            trend_critical = Some(arg.parse().unwrap_or_else(|e: ConfigError| {
+              println!("ERROR: \"-trendcritical {}\" can not be parsed: {}", arg, String::from(e));
+              std::process::exit(3);
+            }));
+          },
+          "-distinct" => {
+            distinct_group = Some(arg.to_string());
+          },
+          "-distinctwarning" => {
+            distinct_warning = Some(arg.parse().unwrap_or_else(|e| {
+              println!("ERROR: \"-distinctwarning {}\" can not be parsed due to {:?}", arg, e);
+              std::process::exit(3);
+            }));
+          },
+          "-distinctcritical" => {
+            distinct_critical = Some(arg.parse().unwrap_or_else(|e| {
+              println!("ERROR: \"-distinctcritical {}\" can not be parsed due to {:?}", arg, e);
+              std::process::exit(3);
+            }));
+          },
+          "-latencycritical" => {
+            latency_critical = Some(arg.parse().unwrap_or_else(|e| {
+              println!("ERROR: \"-latencycritical {}\" can not be parsed due to {:?}", arg, e);
+              std::process::exit(3);
+            }));
+          },
           &_ => {
             // unexpected arguments don't crash the program, as they also don't crash
             // the original script.
@@ -135,7 +551,35 @@ pub fn parse() -> Result<Config, String> {
     curr_arg += 1;
   }
 
-  let conf = Config::new(
+  for list in &logfile_lists {
+    logfiles.extend(read_logfile_list(list)?);
+  }
+  // stdin is only supported for the journal, which is checked below
+  let stdin = logfiles.iter().any(|logfile| logfile == "-");
+  logfiles.retain(|logfile| logfile != "-");
+  let logfile = if logfiles.is_empty() { String::from("") } else { logfiles.remove(0) };
+
+  // without pattern only the requests of access logs are checked
+  let has_request_thresholds = error_rate_warning.is_some() || error_rate_critical.is_some()
+    || latency_warning.is_some() || latency_critical.is_some();
+  if search_pattern.is_empty() && has_request_thresholds {
+    search_pattern = String::from(".*");
+    max_critical_matches = u64::MAX;
+    max_warning_matches = u64::MAX;
+  }
+
+  // the distinct values replace the number of matches
+  if (distinct_warning.is_some() || distinct_critical.is_some()) && !match_thresholds_given {
+    max_critical_matches = u64::MAX;
+    max_warning_matches = u64::MAX;
+  }
+
+  // with filters the pattern is optional
+  if search_pattern.is_empty() && (!filters.is_empty() || max_severity.is_some()) {
+    search_pattern = String::from(".*");
+  }
+
+  let mut conf = Config::new(
     interval_to_check,
     search_pattern,
     logfile,
@@ -147,5 +591,73 @@ pub fn parse() -> Result<Config, String> {
     debug,
     verbose,
   )?;
+
+  for logfile in logfiles {
+    conf.add_logfile(logfile)?;
+  }
+  for logdir in logdirs {
+    conf.add_logdir(logdir);
+  }
+  conf.stdin = stdin;
+  for pattern in &include {
+    conf.add_include(pattern)?;
+  }
+  conf.max_depth = max_depth;
+  conf.locale = locale;
+  conf.encoding = encoding;
+  conf.skew_tolerance = skew_tolerance;
+  conf.multiline = multiline;
+  conf.future_tolerance = future_tolerance;
+  conf.exclude_future = exclude_future;
+  conf.future_state = future_state;
+  if !rotations.is_empty() {
+    conf.rotations = rotations;
+  }
+  conf.active_file_age = active_file_age;
+  conf.truncated_state = truncated_state;
+  conf.io_error_state = io_error_state;
+  conf.stale_warning = stale_warning;
+  conf.stale_critical = stale_critical;
+  conf.line_format = line_format;
+  conf.time_field = time_field;
+  conf.match_fields = match_fields;
+  for filter in &filters {
+    conf.add_filter(filter)?;
+  }
+  if let Some(severity) = max_severity {
+    conf.set_max_severity(&severity)?;
+  }
+  if let Some(classes) = error_classes {
+    conf.set_error_classes(&classes)?;
+  }
+  conf.warning_percentage = warning_percentage;
+  conf.critical_percentage = critical_percentage;
+  conf.error_rate_warning = error_rate_warning;
+  conf.error_rate_critical = error_rate_critical;
+  conf.latency_warning = latency_warning;
+  conf.latency_critical = latency_critical;
+  conf.burst_minutes = burst_minutes;
+  conf.burst_warning = burst_warning;
+  conf.burst_critical = burst_critical;
+  conf.rate_warning = rate_warning;
+  conf.rate_critical = rate_critical;
+  conf.trend_warning = trend_warning;
+  conf.trend_critical = trend_critical;
+  if let Some(group) = distinct_group {
+    conf.set_distinct_group(&group)?;
+  }
+  conf.distinct_approximate = distinct_approximate;
+  conf.distinct_warning = distinct_warning;
+  conf.distinct_critical = distinct_critical;
+  for pattern in &exclude {
+    conf.add_exclude(pattern)?;
+  }
+
+  for (i, pattern) in fallback_patterns.into_iter().enumerate() {
+    let position = fallback_positions.get(i).cloned().unwrap_or(0);
+    conf.add_date_format(pattern, position);
+  }
+
+  conf.validate()?;
   Ok(conf)
 }
@@ -1,19 +1,142 @@
 extern crate fancy_regex;
 
 use self::fancy_regex::Regex;
+use glob::Pattern;
+use std::fs;
+use std::io;
+use std::str::FromStr;
+pub use discovery::Rotation;
+use encoding::Encoding;
+use format::{severity_code, FieldFilter, LineFormat};
+pub use locale::Locale;
+use State;
 
 pub struct Config {
   pub interval_to_check: u64,
   pub search_pattern: String,
   pub logfile: String,
+  /// all log files (or globs) which are searched, the first one is `logfile`
+  pub logfiles: Vec<String>,
+  /// directories which are searched recursively for log files
+  pub logdirs: Vec<String>,
+  /// in directories only files matching one of these globs are searched,
+  /// all files if empty
+  pub include: Vec<Pattern>,
+  /// how many directory levels below a log directory are searched, 1
+  /// means only the files directly in it
+  pub max_depth: Option<usize>,
 
   pub max_critical_matches: u64,
   pub max_warning_matches: u64,
+  /// percentages of matches among the lines in the interval which raise a
+  /// warning or critical state, in addition to the number of matches
+  pub warning_percentage: Option<f64>,
+  pub critical_percentage: Option<f64>,
   pub date_pattern: String,
   pub timeposition: usize,
   pub debug: bool,
   pub verbose: bool,
   pub re: Regex,
+
+  /// Date formats which are tried in order on each line. The first one
+  /// is always built from `date_pattern` and `timeposition`.
+  pub date_formats: Vec<DateFormat>,
+  /// language of month and weekday names in the dates
+  pub locale: Locale,
+  /// seconds a line may be older than the interval before the search in a
+  /// file is stopped, allows for slightly out-of-order lines.
+  pub skew_tolerance: u64,
+  /// lines without timestamp belong to the preceding line with timestamp,
+  /// the pattern is matched against the whole entry.
+  pub multiline: bool,
+  /// seconds a timestamp may be ahead of now before the line is counted
+  /// as a line from the future
+  pub future_tolerance: u64,
+  /// don't match lines with a timestamp in the future
+  pub exclude_future: bool,
+  /// state if most recent lines of a file are from the future
+  pub future_state: State,
+  /// naming schemes of rotated log files which are searched as well
+  pub rotations: Vec<Rotation>,
+  /// files matching one of these glob patterns are never searched
+  pub exclude: Vec<Pattern>,
+  /// character encoding of the log files
+  pub encoding: Encoding,
+  /// rotated files modified within this many seconds are read instead of
+  /// being mapped to memory, since they may be truncated during the search.
+  /// the log files themselves are always read.
+  pub active_file_age: u64,
+  /// state if a file was truncated during the search
+  pub truncated_state: State,
+  /// state if files can not be read, e.g. because of missing permissions
+  pub io_error_state: State,
+  /// minutes after which a log which isn't written anymore raises a
+  /// warning or critical state
+  pub stale_warning: Option<u64>,
+  pub stale_critical: Option<u64>,
+  /// structure of the lines, e.g. json
+  pub line_format: LineFormat,
+  /// field of structured lines with the timestamp, common names are tried
+  /// if not set
+  pub time_field: Option<String>,
+  /// fields of structured lines the pattern is matched against, the whole
+  /// line if empty
+  pub match_fields: Vec<String>,
+  /// conditions on fields of structured lines which all need to be met
+  pub filters: Vec<FieldFilter>,
+  /// only records with this or a more severe syslog severity match
+  pub max_severity: Option<u8>,
+  /// the journal is read from stdin, only supported with the journal format
+  pub stdin: bool,
+  /// status classes of access logs which count as errors, 5 for 5xx
+  pub error_classes: Vec<usize>,
+  /// percentages of error responses which raise a warning or critical state
+  pub error_rate_warning: Option<f64>,
+  pub error_rate_critical: Option<f64>,
+  /// average response times in milliseconds which raise a warning or
+  /// critical state
+  pub latency_warning: Option<u64>,
+  pub latency_critical: Option<u64>,
+  /// length in minutes of the sliding window in which bursts are counted
+  pub burst_minutes: u64,
+  /// matches within the sliding window which raise a warning or critical
+  /// state
+  pub burst_warning: Option<u64>,
+  pub burst_critical: Option<u64>,
+  /// average matches per minute of the interval which raise a warning or
+  /// critical state
+  pub rate_warning: Option<f64>,
+  pub rate_critical: Option<f64>,
+  /// growth of the matches compared to the previous interval which raises
+  /// a warning or critical state. the previous interval is only searched
+  /// if one is set.
+  pub trend_warning: Option<Growth>,
+  pub trend_critical: Option<Growth>,
+  /// index of the capture group whose distinct values are counted
+  pub distinct_group: Option<usize>,
+  pub distinct_name: String,
+  /// estimate the number of distinct values instead of keeping them all
+  pub distinct_approximate: bool,
+  /// numbers of distinct values which raise a warning or critical state
+  pub distinct_warning: Option<u64>,
+  pub distinct_critical: Option<u64>,
+}
+
+/// A date pattern and the index of the whitespace separated field
+/// on the line at which it starts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DateFormat {
+  pub pattern: String,
+  pub position: usize,
+  /// number of whitespace separated fields the pattern spans
+  pub fields: usize,
+}
+
+impl DateFormat {
+  pub fn new(pattern: String, position: usize) -> DateFormat {
+    let fields = pattern.split_whitespace().count();
+    DateFormat { pattern, position, fields }
+  }
 }
 
 impl Config {
@@ -29,9 +152,6 @@ impl Config {
     debug: bool,
     verbose: bool,
   ) -> Result<Config, ConfigError> {
-    if logfile.is_empty() {
-      return Err(ConfigError::LogfileRequired);
-    }
     if search_pattern.is_empty() {
       return Err(ConfigError::PatternRequired);
     }
@@ -45,37 +165,359 @@ impl Config {
       date_pattern = String::from("%Y-%m-%d %H:%M:%S");
     }
 
+    let date_formats = vec![DateFormat::new(date_pattern.clone(), timeposition)];
+    // an empty log file is allowed if log directories are added later on,
+    // `validate` fails if there is neither.
+    let logfiles = if logfile.is_empty() { Vec::new() } else { vec![logfile.clone()] };
+
     Ok(Config {
       interval_to_check,
       search_pattern: search_pattern.to_owned(),
       logfile,
+      logfiles,
+      logdirs: Vec::new(),
+      include: Vec::new(),
+      max_depth: None,
 
       max_critical_matches,
       max_warning_matches,
+      warning_percentage: None,
+      critical_percentage: None,
       date_pattern,
       timeposition,
       debug,
       verbose,
-      re: Regex::new(&search_pattern.to_owned()).expect("regex cannot be created"),
+      re: Regex::new(&capture_groups(&search_pattern).0).expect("regex cannot be created"),
+      date_formats,
+      locale: Locale::En,
+      skew_tolerance: 0,
+      multiline: false,
+      future_tolerance: 60,
+      exclude_future: false,
+      future_state: State::Warning,
+      rotations: vec![Rotation::Exact, Rotation::Numeric, Rotation::DateExt, Rotation::Old],
+      exclude: Vec::new(),
+      encoding: Encoding::Utf8,
+      active_file_age: 60,
+      truncated_state: State::Warning,
+      io_error_state: State::Unknown,
+      stale_warning: None,
+      stale_critical: None,
+      line_format: LineFormat::Plain,
+      time_field: None,
+      match_fields: Vec::new(),
+      filters: Vec::new(),
+      max_severity: None,
+      stdin: false,
+      error_classes: vec![5],
+      error_rate_warning: None,
+      error_rate_critical: None,
+      latency_warning: None,
+      latency_critical: None,
+      burst_minutes: 1,
+      burst_warning: None,
+      burst_critical: None,
+      rate_warning: None,
+      rate_critical: None,
+      trend_warning: None,
+      trend_critical: None,
+      distinct_group: None,
+      distinct_name: String::new(),
+      distinct_approximate: false,
+      distinct_warning: None,
+      distinct_critical: None,
     })
   }
+
+  /// Counts the distinct values of the capture group `(?P<name>...)` of
+  /// the pattern among the matches.
+  pub fn set_distinct_group(&mut self, name: &str) -> Result<(), ConfigError> {
+    let (_, names) = capture_groups(&self.search_pattern);
+    let index = names.iter().position(|n| n == name).ok_or(ConfigError::GroupUnknown)?;
+    self.distinct_group = Some(index + 1);
+    self.distinct_name = name.to_owned();
+    Ok(())
+  }
+
+  /// Whether -warning or -critical is a percentage of the lines.
+  pub fn has_percentages(&self) -> bool {
+    self.warning_percentage.is_some() || self.critical_percentage.is_some()
+  }
+
+  /// Whether -warning or -critical can raise a state, they are disabled
+  /// when only the requests or the distinct values are checked.
+  pub fn has_match_thresholds(&self) -> bool {
+    self.max_warning_matches != u64::MAX || self.max_critical_matches != u64::MAX || self.has_percentages()
+  }
+
+  /// Whether the error rate or the response time of requests is checked.
+  pub fn has_request_thresholds(&self) -> bool {
+    self.error_rate_warning.is_some() || self.error_rate_critical.is_some()
+      || self.latency_warning.is_some() || self.latency_critical.is_some()
+  }
+
+  /// Minutes before now which are searched, the previous interval is added
+  /// for trends.
+  pub fn lookback_minutes(&self) -> u64 {
+    if self.trend_warning.is_some() || self.trend_critical.is_some() {
+      self.interval_to_check * 2
+    } else {
+      self.interval_to_check
+    }
+  }
+
+  /// Checks the options which can only be checked once all of them are
+  /// set, e.g. that there is a log file or directory to search.
+  pub fn validate(&self) -> Result<(), ConfigError> {
+    if self.logfiles.is_empty() && self.logdirs.is_empty() && !self.stdin {
+      return Err(ConfigError::LogfileRequired);
+    }
+    if self.stdin && self.line_format != LineFormat::Journal {
+      return Err(ConfigError::StdinUnsupported);
+    }
+    if self.burst_minutes < 1 {
+      return Err(ConfigError::BurstMinutesInvalid);
+    }
+    Ok(())
+  }
+
+  /// Adds a further log file (or glob) whose matches are added to the
+  /// matches of the other files.
+  pub fn add_logfile(&mut self, logfile: String) -> Result<(), ConfigError> {
+    if logfile == "-" {
+      return Err(ConfigError::StdinUnsupported);
+    }
+    if !self.logfiles.contains(&logfile) {
+      self.logfiles.push(logfile);
+    }
+    Ok(())
+  }
+
+  /// Adds a directory which is searched recursively for log files.
+  pub fn add_logdir(&mut self, logdir: String) {
+    if !self.logdirs.contains(&logdir) {
+      self.logdirs.push(logdir);
+    }
+  }
+
+  /// In log directories only files whose name or path relative to the
+  /// directory match one of the included globs are searched.
+  pub fn add_include(&mut self, pattern: &str) -> Result<(), ConfigError> {
+    let pattern = Pattern::new(pattern).map_err(|_| ConfigError::IncludeInvalid)?;
+    self.include.push(pattern);
+    Ok(())
+  }
+
+  /// Excludes files whose path or file name match the glob `pattern`.
+  pub fn add_exclude(&mut self, pattern: &str) -> Result<(), ConfigError> {
+    let pattern = Pattern::new(pattern).map_err(|_| ConfigError::ExcludeInvalid)?;
+    self.exclude.push(pattern);
+    Ok(())
+  }
+
+  /// Adds a condition on a field of structured lines, either `field=value`
+  /// or `field~regex`.
+  pub fn add_filter(&mut self, filter: &str) -> Result<(), ConfigError> {
+    self.filters.push(filter.parse()?);
+    Ok(())
+  }
+
+  /// Only records with the syslog severity `severity` (e.g. `err`) or a
+  /// more severe one match.
+  pub fn set_max_severity(&mut self, severity: &str) -> Result<(), ConfigError> {
+    self.max_severity = Some(severity_code(severity).ok_or(ConfigError::SeverityInvalid)?);
+    Ok(())
+  }
+
+  /// Sets the status classes which count as errors from a comma separated
+  /// list like `5xx,4xx`.
+  pub fn set_error_classes(&mut self, classes: &str) -> Result<(), ConfigError> {
+    self.error_classes = classes.split(',')
+      .map(|class| match class.trim().to_lowercase().trim_end_matches("xx").parse() {
+        Ok(class) if (1..6).contains(&class) => Ok(class),
+        _ => Err(ConfigError::StatusClassInvalid),
+      })
+      .collect::<Result<_, _>>()?;
+    Ok(())
+  }
+
+  /// Adds a date format which is tried if none of the previously
+  /// added formats can be parsed from a line.
+  pub fn add_date_format(&mut self, pattern: String, position: usize) {
+    self.date_formats.push(DateFormat::new(pattern, position));
+  }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Copy)]
+/// fancy-regex 0.1 doesn't support named groups, so `(?P<name>...)` and
+/// `(?<name>...)` are replaced with unnamed groups. This is done for every
+/// search pattern. Returns the pattern and
+/// the names of all capture groups in order, unnamed groups have an empty
+/// name.
+fn capture_groups(pattern: &str) -> (String, Vec<String>) {
+  let mut stripped = String::with_capacity(pattern.len());
+  let mut names = Vec::new();
+  let mut in_class = false;
+  let mut chars = pattern.char_indices();
+
+  while let Some((i, c)) = chars.next() {
+    stripped.push(c);
+    match c {
+      '\\' => {
+        if let Some((_, escaped)) = chars.next() {
+          stripped.push(escaped);
+        }
+      },
+      '[' if !in_class => in_class = true,
+      ']' if in_class => in_class = false,
+      '(' if !in_class => {
+        let rest = &pattern[i + 1..];
+        let name_start = if rest.starts_with("?P<") {
+          3
+        } else if rest.starts_with("?<") && !rest.starts_with("?<=") && !rest.starts_with("?<!") {
+          2
+        } else if rest.starts_with('?') {
+          // non-capturing groups, lookarounds and flags
+          continue;
+        } else {
+          names.push(String::new());
+          continue;
+        };
+
+        match rest[name_start..].find('>') {
+          Some(end) => {
+            names.push(rest[name_start..name_start + end].to_owned());
+            // skip the name, `(` is already pushed
+            for _ in rest[..name_start + end + 1].chars() {
+              chars.next();
+            }
+          },
+          None => names.push(String::new()),
+        }
+      },
+      _ => {},
+    }
+  }
+
+  (stripped, names)
+}
+
+/// By how much the matches may grow compared to the previous interval,
+/// e.g. `10` matches or `50%`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Growth {
+  Matches(u64),
+  /// an empty previous interval counts as one match
+  Percent(f64),
+}
+
+impl Growth {
+  pub fn is_exceeded(&self, previous: u64, current: u64) -> bool {
+    match *self {
+      Growth::Matches(matches) => current > previous.saturating_add(matches),
+      Growth::Percent(percent) => {
+        let growth = (current as f64 - previous as f64) * 100.0 / previous.max(1) as f64;
+        growth > percent
+      },
+    }
+  }
+}
+
+impl FromStr for Growth {
+  type Err = ConfigError;
+
+  fn from_str(s: &str) -> Result<Growth, ConfigError> {
+    match s.strip_suffix('%') {
+      Some(percent) => percent.parse().map(Growth::Percent).map_err(|_| ConfigError::GrowthInvalid),
+      None => s.parse().map(Growth::Matches).map_err(|_| ConfigError::GrowthInvalid),
+    }
+  }
+}
+
+/// Reads a file with one log file path or glob per line. Empty lines and
+/// lines starting with `#` are skipped.
+pub fn read_logfile_list(path: &str) -> Result<Vec<String>, ConfigError> {
+  let content = fs::read_to_string(path)
+    .map_err(|e| ConfigError::LogfileListUnreadable(path.to_owned(), e.kind()))?;
+  let logfiles = content.lines()
+    .map(|line| line.trim())
+    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+    .map(|line| line.to_owned())
+    .collect();
+  Ok(logfiles)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ConfigError {
    LogfileRequired,
    PatternRequired,
    IntervalInvalid,
    StdinUnsupported,
+   LocaleUnsupported,
+   StateInvalid,
+   RotationInvalid,
+   ExcludeInvalid,
+   /// the path of the list and why it can't be read
+   LogfileListUnreadable(String, io::ErrorKind),
+   IncludeInvalid,
+   EncodingUnsupported,
+   FormatUnsupported,
+   FilterInvalid,
+   SeverityInvalid,
+   StatusClassInvalid,
+   BurstMinutesInvalid,
+   GrowthInvalid,
+   GroupUnknown,
 }
 
 impl From<ConfigError> for String {
   fn from(error: ConfigError) -> Self {
     match error {
-      ConfigError::LogfileRequired => "no -logfile".to_owned(),
+      ConfigError::LogfileRequired => "no -logfile or -logdir".to_owned(),
       ConfigError::PatternRequired => "no -pattern".to_owned(),
       ConfigError::IntervalInvalid => "interval needs to be set and be >= 1".to_owned(),
-      ConfigError::StdinUnsupported => "stdin as path is not supported".to_owned(),
+      ConfigError::StdinUnsupported => "stdin as path is only supported with -format journal".to_owned(),
+      ConfigError::LocaleUnsupported => "locale needs to be one of en, de, fr, es, it, nl".to_owned(),
+      ConfigError::StateInvalid => "state needs to be one of ok, warning, critical, unknown".to_owned(),
+      ConfigError::RotationInvalid => "rotation needs to be a list of any, exact, numeric, dateext, old".to_owned(),
+      ConfigError::ExcludeInvalid => "exclude needs to be a valid glob pattern".to_owned(),
+      ConfigError::LogfileListUnreadable(path, kind) => format!("-logfilelist {} can not be read: {}", path, kind),
+      ConfigError::IncludeInvalid => "include needs to be a valid glob pattern".to_owned(),
+      ConfigError::EncodingUnsupported => "encoding needs to be one of utf-8, utf-8-lossy, latin1, windows-1252, utf-16le".to_owned(),
+      ConfigError::FormatUnsupported => "format needs to be one of plain, json, logfmt, docker, cri, syslog, journal, access".to_owned(),
+      ConfigError::FilterInvalid => "filter needs to be field=value or field~regex".to_owned(),
+      ConfigError::SeverityInvalid => "severity needs to be one of emerg, alert, crit, err, warning, notice, info, debug".to_owned(),
+      ConfigError::StatusClassInvalid => "status classes need to be a list of 1xx, 2xx, 3xx, 4xx, 5xx".to_owned(),
+      ConfigError::BurstMinutesInvalid => "-burstminutes needs to be >= 1".to_owned(),
+      ConfigError::GrowthInvalid => "growth needs to be a number of matches or a percentage like 50%".to_owned(),
+      ConfigError::GroupUnknown => "-distinct needs to be the name of a group (?P<name>...) of the pattern".to_owned(),
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn should_strip_the_names_of_capture_groups() {
+    // given
+    let groups = |pattern: &str| {
+      let (stripped, names) = capture_groups(pattern);
+      (stripped, names.join(","))
+    };
+
+    // when
+    let named = groups(r"Failed (?:password) for (\S+) from (?P<ip>\S+) port (?<port>\d+)");
+    let escaped = groups(r"\(not a group\) \\(?P<user>\w+)");
+    let class = groups(r"[(?P<x>)\]] (a)");
+    let lookarounds = groups(r"(?<=from )(?<!x)(?i)(?P<ip>\S+)");
+    let unterminated = groups(r"(?P<ip");
+
+    // then
+    assert_eq!(named, (r"Failed (?:password) for (\S+) from (\S+) port (\d+)".to_owned(), ",ip,port".to_owned()));
+    assert_eq!(escaped, (r"\(not a group\) \\(\w+)".to_owned(), "user".to_owned()));
+    assert_eq!(class, (r"[(?P<x>)\]] (a)".to_owned(), "".to_owned()));
+    assert_eq!(lookarounds, (r"(?<=from )(?<!x)(?i)(\S+)".to_owned(), "ip".to_owned()));
+    assert_eq!(unterminated, (r"(?P<ip".to_owned(), "".to_owned()));
+  }
+}
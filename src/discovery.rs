@@ -0,0 +1,461 @@
+extern crate fancy_regex;
+
+use self::fancy_regex::Regex;
+use glob::glob;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::UNIX_EPOCH;
+use Config;
+use ConfigError;
+
+/// Naming scheme of rotated log files. The log file itself is always
+/// searched, the schemes decide which of the other files in the same
+/// directory are rotated versions of it. By default all schemes except
+/// `Any` and `Regex` are used.
+pub enum Rotation {
+  /// any file starting with the log file name, like the original plugin,
+  /// only used if it is passed explicitly
+  Any,
+  /// only the log file itself
+  Exact,
+  /// `app.log.1`, `app.log.2`, ...
+  Numeric,
+  /// `app.log-20180913`, as written by logrotate's `dateext`
+  DateExt,
+  /// `app.log.old`
+  Old,
+  /// any file in the directory whose name matches the regex
+  Regex(Regex),
+}
+
+impl FromStr for Rotation {
+  type Err = ConfigError;
+
+  fn from_str(s: &str) -> Result<Rotation, ConfigError> {
+    match s.to_lowercase().as_str() {
+      "any" => Ok(Rotation::Any),
+      "exact" => Ok(Rotation::Exact),
+      "numeric" => Ok(Rotation::Numeric),
+      "dateext" => Ok(Rotation::DateExt),
+      "old" => Ok(Rotation::Old),
+      _ => Err(ConfigError::RotationInvalid),
+    }
+  }
+}
+
+impl Rotation {
+  pub fn from_regex(pattern: &str) -> Result<Rotation, ConfigError> {
+    let re = Regex::new(pattern).map_err(|_| ConfigError::RotationInvalid)?;
+    Ok(Rotation::Regex(re))
+  }
+
+  /// Checks if `name` is a rotated version of the file `base`. Both are
+  /// file names without the directory.
+  fn is_rotated(&self, base: &str, name: &str) -> bool {
+    if let Rotation::Regex(re) = self {
+      return re.is_match(name).unwrap_or(false);
+    }
+
+    let suffix = match name.strip_prefix(base) {
+      Some(suffix) => suffix,
+      None => return false,
+    };
+    let all_digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+
+    match self {
+      Rotation::Any => true,
+      Rotation::Exact => suffix.is_empty(),
+      Rotation::Numeric => suffix.strip_prefix('.').is_some_and(all_digits),
+      Rotation::DateExt => {
+        // `-%Y%m%d` or `-%Y%m%d%H`
+        suffix.strip_prefix('-').is_some_and(|d| all_digits(d) && (d.len() == 8 || d.len() == 10))
+      },
+      Rotation::Old => suffix == ".old",
+      Rotation::Regex(_) => false,
+    }
+  }
+}
+
+/// Returns all files which belong to the log file `logfile`, sorted by path.
+/// `logfile` may itself be a glob pattern.
+pub fn find_files(conf: &Config, logfile: &str) -> Result<Vec<PathBuf>, String> {
+  let mut files = Vec::new();
+
+  let legacy = conf.rotations.iter().any(|r| matches!(r, Rotation::Any));
+  if legacy {
+    // the original plugin just appends a `*` to the file name
+    let mut exp = logfile.to_owned();
+    exp.push('*');
+    if conf.debug {
+      println!("looking for files matching {}", exp);
+    }
+    files.extend(expand(&exp)?);
+  }
+
+  for base in find_live_files(logfile)? {
+    if base.exists() {
+      files.push(base.clone());
+    }
+    files.extend(find_rotated(conf, &base));
+  }
+
+  files.retain(|path| !is_excluded(conf, path));
+  files.sort();
+  files.dedup();
+  Ok(files)
+}
+
+/// Returns the files which `logfile` names itself, i.e. without their
+/// rotated versions. These are the files which are still written to.
+pub fn find_live_files(logfile: &str) -> Result<Vec<PathBuf>, String> {
+  let mut files = expand(logfile)?;
+  if files.is_empty() && !logfile.contains(&['*', '?', '['][..]) {
+    // the log file itself may be missing right after a rotation
+    files.push(PathBuf::from(logfile));
+  }
+  Ok(files)
+}
+
+/// Groups the files of a log file into rotation chains, i.e. each of the
+/// `live` files together with its rotated versions. Files which are no
+/// rotated version of a live file, e.g. further files matched by a glob or by
+/// `Rotation::Any`, are a chain on their own. The search of a chain stops at
+/// the first line which is too old, so unrelated files must not share one.
+pub fn rotation_chains(conf: &Config, files: Vec<PathBuf>, live: &[PathBuf]) -> Vec<Vec<PathBuf>> {
+  let mut chains: BTreeMap<PathBuf, Vec<PathBuf>> = BTreeMap::new();
+  for path in files {
+    let owner = live_owner(conf, &path, live).unwrap_or(&path).clone();
+    chains.entry(owner).or_default().push(path);
+  }
+  chains.into_values().collect()
+}
+
+/// The live file of which `path` is a rotated version. If there are several,
+/// the one with the longest name is the most specific one.
+fn live_owner<'a>(conf: &Config, path: &Path, live: &'a [PathBuf]) -> Option<&'a PathBuf> {
+  let file_name = |path: &Path| path.file_name().and_then(|n| n.to_str()).unwrap_or("").to_owned();
+  let name = file_name(path);
+
+  live.iter()
+    .filter(|base| base.as_path() != path && base.parent() == path.parent())
+    .filter(|base| {
+      let base_name = file_name(base);
+      // anything that starts with the name is no proof of a rotation
+      conf.rotations.iter().any(|r| !matches!(r, Rotation::Any) && r.is_rotated(&base_name, &name))
+    })
+    .max_by_key(|base| file_name(base).len())
+}
+
+/// Returns all files below the directory `logdir` which match the included
+/// and don't match the excluded globs, sorted by path. Symbolic links to
+/// directories are not followed.
+pub fn walk_dir(conf: &Config, logdir: &str) -> Vec<PathBuf> {
+  let root = Path::new(logdir);
+  let mut files = Vec::new();
+  let mut dirs = vec![(root.to_path_buf(), 1)];
+
+  while let Some((dir, depth)) = dirs.pop() {
+    let entries = match fs::read_dir(&dir) {
+      Ok(entries) => entries,
+      Err(e) => {
+        if conf.debug {
+          println!("cannot read directory {:?}: {}", dir, e);
+        }
+        continue;
+      }
+    };
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+      let path = entry.path();
+      let file_type = match entry.file_type() {
+        Ok(file_type) => file_type,
+        Err(_) => continue,
+      };
+
+      if file_type.is_dir() {
+        if conf.max_depth.is_none_or(|max| depth < max) {
+          dirs.push((path, depth + 1));
+        }
+      } else if path.is_file() && is_included(conf, root, &path) && !is_excluded(conf, &path) {
+        files.push(path);
+      }
+    }
+  }
+
+  files.sort();
+  files
+}
+
+fn is_included(conf: &Config, root: &Path, path: &Path) -> bool {
+  if conf.include.is_empty() {
+    return true;
+  }
+  let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+  let relative = path.strip_prefix(root).unwrap_or(path);
+  conf.include.iter().any(|p| p.matches(name) || p.matches_path(relative))
+}
+
+/// Identifies a file independent of the path through which it was found.
+#[cfg(unix)]
+pub type FileId = (u64, u64);
+#[cfg(not(unix))]
+pub type FileId = PathBuf;
+
+/// Returns the device and inode of the file, symbolic links are followed.
+#[cfg(unix)]
+pub fn file_id(path: &Path) -> Option<FileId> {
+  use std::os::unix::fs::MetadataExt;
+  fs::metadata(path).ok().map(|m| (m.dev(), m.ino()))
+}
+
+#[cfg(not(unix))]
+pub fn file_id(path: &Path) -> Option<FileId> {
+  fs::canonicalize(path).ok()
+}
+
+/// Sorts the files so that the most recently modified one comes first.
+/// Files with the same modification time are ordered by their rotation
+/// index, i.e. `app.log.2` comes before `app.log.10`.
+pub fn sort_newest_first(files: &mut [PathBuf]) {
+  files.sort_by_cached_key(|path| {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).unwrap_or(UNIX_EPOCH);
+    (Reverse(modified), rotation_index(path), path.clone())
+  });
+}
+
+/// The number at the end of the file name, e.g. `2` for `app.log.2`.
+fn rotation_index(path: &Path) -> u64 {
+  let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+  let digits = name.len() - name.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+  name[name.len() - digits..].parse().unwrap_or(0)
+}
+
+fn expand(exp: &str) -> Result<Vec<PathBuf>, String> {
+  let entries = glob(exp).map_err(|e| format!("failed to read glob pattern: {}", e))?;
+
+  let mut files = Vec::new();
+  for entry in entries {
+    match entry {
+      Ok(path) => files.push(path),
+      Err(e) => println!("ERROR: {:?}", e),
+    }
+  }
+  Ok(files)
+}
+
+fn find_rotated(conf: &Config, base: &Path) -> Vec<PathBuf> {
+  let base_name = match base.file_name().and_then(|n| n.to_str()) {
+    Some(name) => name,
+    None => return Vec::new(),
+  };
+  let dir = match base.parent() {
+    Some(dir) if dir.as_os_str().is_empty() => Path::new("."),
+    Some(dir) => dir,
+    None => return Vec::new(),
+  };
+
+  let entries = match fs::read_dir(dir) {
+    Ok(entries) => entries,
+    Err(e) => {
+      if conf.debug {
+        println!("cannot read directory {:?}: {}", dir, e);
+      }
+      return Vec::new();
+    }
+  };
+
+  entries
+    .filter_map(|entry| entry.ok())
+    .filter(|entry| {
+      let name = entry.file_name();
+      let name = match name.to_str() {
+        Some(name) => name,
+        None => return false,
+      };
+      name != base_name && conf.rotations.iter().any(|r| r.is_rotated(base_name, name))
+    })
+    .map(|entry| base.with_file_name(entry.file_name()))
+    .collect()
+}
+
+fn is_excluded(conf: &Config, path: &Path) -> bool {
+  let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+  let excluded = conf.exclude.iter().any(|p| p.matches_path(path) || p.matches(name));
+  if excluded && conf.debug {
+    println!("excluding {:?}", path);
+  }
+  excluded
+}
+
+#[cfg(test)]
+mod tests {
+  extern crate filetime;
+  extern crate tempfile;
+
+  use super::*;
+  use self::tempfile::TempDir;
+  use std::fs::File;
+
+  fn create_files(names: &[&str]) -> TempDir {
+    let dir = TempDir::new().expect("not able to create tempdir");
+    for name in names {
+      File::create(dir.path().join(name)).expect("not able to create file");
+    }
+    dir
+  }
+
+  fn find(dir: &TempDir, conf: &Config) -> Vec<String> {
+    let logfile = dir.path().join("app.log");
+    find_files(conf, logfile.to_str().unwrap()).unwrap()
+      .iter()
+      .map(|p| p.file_name().unwrap().to_str().unwrap().to_owned())
+      .collect()
+  }
+
+  fn get_conf(rotations: Vec<Rotation>) -> Config {
+    let mut conf = Config::new(1, "foo".to_owned(), "app.log".to_owned(), 1, 1, "".to_owned(), 0, false, false).unwrap();
+    conf.rotations = rotations;
+    conf
+  }
+
+  const FILES: &[&str] = &[
+    "app.log", "app.log.1", "app.log.12", "app.log.1.gz", "app.log-20180913",
+    "app.log.old", "app.log-audit", "application.log", "other.log",
+  ];
+
+  #[test]
+  fn should_find_rotated_files_by_default() {
+    // given
+    let dir = create_files(FILES);
+    let conf = Config::new(1, "foo".to_owned(), "app.log".to_owned(), 1, 1, "".to_owned(), 0, false, false).unwrap();
+
+    // when
+    let files = find(&dir, &conf);
+
+    // then
+    assert_eq!(files, vec!["app.log", "app.log-20180913", "app.log.1", "app.log.12", "app.log.old"]);
+  }
+
+  #[test]
+  fn should_find_all_files_with_prefix_with_any() {
+    // given
+    let dir = create_files(FILES);
+    let conf = get_conf(vec![Rotation::Any]);
+
+    // when
+    let files = find(&dir, &conf);
+
+    // then
+    assert_eq!(files, vec!["app.log", "app.log-20180913", "app.log-audit", "app.log.1",
+                           "app.log.1.gz", "app.log.12", "app.log.old"]);
+  }
+
+  #[test]
+  fn should_only_find_files_of_rotation_schemes() {
+    // given
+    let dir = create_files(FILES);
+
+    // when
+    let exact = find(&dir, &get_conf(vec![Rotation::Exact]));
+    let numeric = find(&dir, &get_conf(vec![Rotation::Numeric]));
+    let dateext_and_old = find(&dir, &get_conf(vec![Rotation::DateExt, Rotation::Old]));
+    let regex = find(&dir, &get_conf(vec![Rotation::Regex(Regex::new(r"^app\.log\.\d+\.gz$").unwrap())]));
+
+    // then
+    assert_eq!(exact, vec!["app.log"]);
+    assert_eq!(numeric, vec!["app.log", "app.log.1", "app.log.12"]);
+    assert_eq!(dateext_and_old, vec!["app.log", "app.log-20180913", "app.log.old"]);
+    assert_eq!(regex, vec!["app.log", "app.log.1.gz"]);
+  }
+
+  #[test]
+  fn should_group_files_into_rotation_chains() {
+    // given
+    let dir = create_files(&["app.log", "app.log.1", "app.log-audit", "db.log", "db.log.old"]);
+    let mut conf = get_conf(vec![Rotation::Any, Rotation::Numeric, Rotation::Old]);
+    let logfile = dir.path().join("*.log");
+    let files = find_files(&conf, logfile.to_str().unwrap()).unwrap();
+    let live = find_live_files(logfile.to_str().unwrap()).unwrap();
+
+    // when
+    let chains = rotation_chains(&conf, files.clone(), &live);
+    conf.rotations = vec![Rotation::Any];
+    let chains_any = rotation_chains(&conf, files, &live);
+
+    // then
+    let names = |chains: Vec<Vec<PathBuf>>| -> Vec<Vec<String>> {
+      chains.iter()
+        .map(|chain| chain.iter().map(|p| p.file_name().unwrap().to_str().unwrap().to_owned()).collect())
+        .collect()
+    };
+    assert_eq!(names(chains), vec![vec!["app.log", "app.log.1"], vec!["app.log-audit"], vec!["db.log", "db.log.old"]]);
+    assert_eq!(names(chains_any).len(), 5);
+  }
+
+  #[test]
+  fn should_sort_newest_first() {
+    // given
+    let dir = create_files(&["app.log", "app.log.2", "app.log.10", "app.log.1"]);
+    let mtime = |name: &str, secs: i64| {
+      let time = filetime::FileTime::from_unix_time(secs, 0);
+      filetime::set_file_times(dir.path().join(name), time, time).unwrap();
+    };
+    mtime("app.log", 3000);
+    mtime("app.log.1", 2000);
+    mtime("app.log.2", 1000);
+    mtime("app.log.10", 1000);
+    let mut files = find_files(&get_conf(vec![Rotation::Numeric]), dir.path().join("app.log").to_str().unwrap()).unwrap();
+
+    // when
+    sort_newest_first(&mut files);
+
+    // then
+    let names: Vec<&str> = files.iter().map(|p| p.file_name().unwrap().to_str().unwrap()).collect();
+    assert_eq!(names, vec!["app.log", "app.log.1", "app.log.2", "app.log.10"]);
+  }
+
+  #[test]
+  fn should_walk_directories() {
+    // given
+    let dir = create_files(&["app.log", "notes.txt"]);
+    for tenant in &["a", "b", "b/archive"] {
+      fs::create_dir(dir.path().join(tenant)).unwrap();
+      File::create(dir.path().join(tenant).join("app.log")).unwrap();
+      File::create(dir.path().join(tenant).join("app.log.1")).unwrap();
+    }
+    let mut conf = get_conf(vec![Rotation::Any]);
+    conf.add_include("*.log").unwrap();
+    conf.add_exclude("*/archive/*").unwrap();
+    let root = dir.path().to_str().unwrap();
+
+    // when
+    let files = walk_dir(&conf, root);
+    conf.max_depth = Some(1);
+    let files_max_depth = walk_dir(&conf, root);
+
+    // then
+    let relative = |files: Vec<PathBuf>| -> Vec<String> {
+      files.iter().map(|p| p.strip_prefix(root).unwrap().display().to_string()).collect()
+    };
+    assert_eq!(relative(files), vec!["a/app.log", "app.log", "b/app.log"]);
+    assert_eq!(relative(files_max_depth), vec!["app.log"]);
+  }
+
+  #[test]
+  fn should_exclude_files() {
+    // given
+    let dir = create_files(FILES);
+    let mut conf = get_conf(vec![Rotation::Any]);
+    conf.add_exclude("*.gz").unwrap();
+    conf.add_exclude("app.log-audit").unwrap();
+
+    // when
+    let files = find(&dir, &conf);
+
+    // then
+    assert_eq!(files, vec!["app.log", "app.log-20180913", "app.log.1", "app.log.12", "app.log.old"]);
+  }
+}
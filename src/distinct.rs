@@ -0,0 +1,117 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+
+/// bits of the hash which select the register of the HyperLogLog
+const PRECISION: u32 = 14;
+const REGISTERS: usize = 1 << PRECISION;
+
+/// The distinct values of a capture group. The values are either kept
+/// exactly or estimated with a HyperLogLog, which needs 16KiB no matter how
+/// many values there are. The error of the estimate is about 1%.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Distinct {
+  values: HashSet<String>,
+  /// the registers of the HyperLogLog, empty if the values are kept exactly
+  registers: Vec<u8>,
+}
+
+impl Distinct {
+  pub fn insert(&mut self, value: &str, approximate: bool) {
+    if !approximate {
+      if !self.values.contains(value) {
+        self.values.insert(value.to_owned());
+      }
+      return;
+    }
+
+    if self.registers.is_empty() {
+      self.registers = vec![0; REGISTERS];
+    }
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    let hash = hasher.finish();
+
+    let index = (hash >> (64 - PRECISION)) as usize;
+    // the bit after the index bits ends the run of zeros at the latest
+    let rest = (hash << PRECISION) | (1 << (PRECISION - 1));
+    let rank = rest.leading_zeros() as u8 + 1;
+    self.registers[index] = self.registers[index].max(rank);
+  }
+
+  /// Adds the values of `other`, e.g. of another file.
+  pub fn merge(&mut self, other: &Distinct) {
+    self.values.extend(other.values.iter().cloned());
+    if self.registers.is_empty() {
+      self.registers = other.registers.clone();
+    } else {
+      for (register, &other) in self.registers.iter_mut().zip(&other.registers) {
+        *register = (*register).max(other);
+      }
+    }
+  }
+
+  pub fn count(&self) -> u64 {
+    if self.registers.is_empty() {
+      return self.values.len() as u64;
+    }
+
+    let m = REGISTERS as f64;
+    let alpha = 0.7213 / (1.0 + 1.079 / m);
+    let sum: f64 = self.registers.iter().map(|&r| 2f64.powi(-(r as i32))).sum();
+    let estimate = alpha * m * m / sum;
+
+    // few values are estimated better by counting the empty registers
+    let zeros = self.registers.iter().filter(|&&r| r == 0).count();
+    if estimate <= 2.5 * m && zeros > 0 {
+      (m * (m / zeros as f64).ln()).round() as u64
+    } else {
+      estimate.round() as u64
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn should_count_distinct_values_exactly() {
+    // given
+    let mut first = Distinct::default();
+    let mut second = Distinct::default();
+
+    // when
+    for value in &["10.0.0.1", "10.0.0.2", "10.0.0.1"] {
+      first.insert(value, false);
+    }
+    second.insert("10.0.0.2", false);
+    second.insert("10.0.0.3", false);
+    first.merge(&second);
+
+    // then
+    assert_eq!(first.count(), 3);
+  }
+
+  #[test]
+  fn should_estimate_distinct_values() {
+    // given
+    let mut first = Distinct::default();
+    let mut second = Distinct::default();
+
+    // when
+    for i in 0..60000 {
+      first.insert(&format!("10.0.{}.{}", i / 256, i % 256), true);
+    }
+    for i in 40000..100000 {
+      second.insert(&format!("10.0.{}.{}", i / 256, i % 256), true);
+    }
+    let only_first = first.clone();
+    first.merge(&second);
+
+    // then
+    let error = (first.count() as f64 - 100000.0).abs() / 100000.0;
+    assert!(error < 0.03, "estimated {}", first.count());
+    assert!((only_first.count() as f64 - 60000.0).abs() / 60000.0 < 0.03);
+  }
+}
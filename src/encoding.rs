@@ -0,0 +1,113 @@
+use std::borrow::Cow;
+use std::str;
+use std::str::FromStr;
+use ConfigError;
+
+/// Character encoding of the log files.
+#[derive(Debug, Clone, PartialEq, Eq, Copy)]
+pub enum Encoding {
+  /// the search in a file stops at the first line which is not valid utf-8
+  Utf8,
+  /// invalid utf-8 sequences are replaced with `U+FFFD`
+  Utf8Lossy,
+  Latin1,
+  Windows1252,
+  Utf16Le,
+}
+
+// windows-1252 differs from latin1 only in the range 0x80 - 0x9f. the
+// five undefined bytes are mapped to the same code points as in latin1.
+const WINDOWS_1252: [char; 32] = [
+  '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž', '\u{8f}',
+  '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}', 'ž', 'Ÿ',
+];
+
+impl Encoding {
+  /// The bytes which end a line.
+  pub fn newline(self) -> &'static [u8] {
+    match self {
+      Encoding::Utf16Le => b"\n\0",
+      _ => b"\n",
+    }
+  }
+
+  /// Decodes a single line. Only strict utf-8 can fail, the other
+  /// encodings replace what can't be decoded.
+  pub fn decode(self, bytes: &[u8]) -> Option<Cow<'_, str>> {
+    match self {
+      Encoding::Utf8 => str::from_utf8(bytes).ok().map(Cow::Borrowed),
+      Encoding::Utf8Lossy => Some(String::from_utf8_lossy(bytes)),
+      Encoding::Latin1 | Encoding::Windows1252 => {
+        if bytes.is_ascii() {
+          return str::from_utf8(bytes).ok().map(Cow::Borrowed);
+        }
+        let decoded = bytes.iter().map(|&b| match b {
+          0x80..=0x9f if self == Encoding::Windows1252 => WINDOWS_1252[(b - 0x80) as usize],
+          _ => b as char,
+        });
+        Some(Cow::Owned(decoded.collect()))
+      },
+      Encoding::Utf16Le => {
+        let units: Vec<u16> = bytes.chunks(2)
+          .map(|pair| u16::from_le_bytes([pair[0], *pair.get(1).unwrap_or(&0)]))
+          .collect();
+        let decoded = String::from_utf16_lossy(&units);
+        // the byte order mark at the beginning of the file
+        match decoded.strip_prefix('\u{feff}') {
+          Some(without_bom) => Some(Cow::Owned(without_bom.to_owned())),
+          None => Some(Cow::Owned(decoded)),
+        }
+      },
+    }
+  }
+}
+
+impl FromStr for Encoding {
+  type Err = ConfigError;
+
+  fn from_str(s: &str) -> Result<Encoding, ConfigError> {
+    match s.to_lowercase().replace('_', "-").as_str() {
+      "utf-8" | "utf8" => Ok(Encoding::Utf8),
+      "utf-8-lossy" | "utf8-lossy" => Ok(Encoding::Utf8Lossy),
+      "latin1" | "latin-1" | "iso-8859-1" => Ok(Encoding::Latin1),
+      "windows-1252" | "cp1252" => Ok(Encoding::Windows1252),
+      "utf-16le" | "utf16le" => Ok(Encoding::Utf16Le),
+      _ => Err(ConfigError::EncodingUnsupported),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn should_decode_legacy_encodings() {
+    // given
+    let bytes = b"caf\xe9 \x80 \xff";
+
+    // when
+    let utf8 = Encoding::Utf8.decode(bytes);
+    let lossy = Encoding::Utf8Lossy.decode(bytes);
+    let latin1 = Encoding::Latin1.decode(bytes);
+    let windows_1252 = Encoding::Windows1252.decode(bytes);
+
+    // then
+    assert_eq!(utf8, None);
+    assert_eq!(lossy.unwrap(), "caf\u{fffd} \u{fffd} \u{fffd}");
+    assert_eq!(latin1.unwrap(), "café \u{80} ÿ");
+    assert_eq!(windows_1252.unwrap(), "café € ÿ");
+  }
+
+  #[test]
+  fn should_decode_utf16le() {
+    // given
+    let bytes = b"\xff\xfec\x00a\x00f\x00\xe9\x00";
+
+    // when
+    let decoded = Encoding::Utf16Le.decode(bytes);
+
+    // then
+    assert_eq!(decoded.unwrap(), "café");
+  }
+}
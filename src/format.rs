@@ -0,0 +1,784 @@
+extern crate fancy_regex;
+
+use self::fancy_regex::Regex;
+use chrono::prelude::*;
+use serde_json;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use utils;
+use {Config, ConfigError};
+
+/// fields which are tried if no -timefield is given
+const DEFAULT_TIME_FIELDS: [&str; 5] = ["ts", "time", "timestamp", "@timestamp", "__REALTIME_TIMESTAMP"];
+
+/// syslog severities, the index is the numerical code
+const SEVERITIES: [&str; 8] = ["emerg", "alert", "crit", "err", "warning", "notice", "info", "debug"];
+
+/// How the lines of the log files are structured.
+#[derive(Debug, Clone, PartialEq, Eq, Copy)]
+pub enum LineFormat {
+  /// the date is taken from whitespace separated fields, the pattern is
+  /// matched against the whole line
+  Plain,
+  /// each line is a json object
+  Json,
+  /// each line consists of `key=value` pairs, values with spaces are quoted
+  Logfmt,
+  /// docker's json-file driver, `{"log":"...\n","stream":"stdout","time":"..."}`
+  Docker,
+  /// the kubernetes container runtime interface, `<time> <stream> <P|F> <log>`
+  Cri,
+  /// syslog lines as in RFC 5424 or RFC 3164, e.g. /var/log/messages
+  Syslog,
+  /// the output of `journalctl -o export` or `journalctl -o json`
+  Journal,
+  /// apache's and nginx's common or combined log format, optionally
+  /// followed by the response time
+  Access,
+}
+
+impl LineFormat {
+  /// Container logs wrap the lines written by the application, long lines
+  /// are split into several partial lines.
+  pub fn is_container(self) -> bool {
+    matches!(self, LineFormat::Docker | LineFormat::Cri)
+  }
+}
+
+impl FromStr for LineFormat {
+  type Err = ConfigError;
+
+  fn from_str(s: &str) -> Result<LineFormat, ConfigError> {
+    match s.to_lowercase().as_str() {
+      "plain" => Ok(LineFormat::Plain),
+      "json" => Ok(LineFormat::Json),
+      "logfmt" => Ok(LineFormat::Logfmt),
+      "docker" => Ok(LineFormat::Docker),
+      "cri" => Ok(LineFormat::Cri),
+      "syslog" => Ok(LineFormat::Syslog),
+      "journal" => Ok(LineFormat::Journal),
+      "access" => Ok(LineFormat::Access),
+      _ => Err(ConfigError::FormatUnsupported),
+    }
+  }
+}
+
+/// A condition on a single field of a record, `field=value` compares the
+/// value, `field~regex` matches the regex against it.
+pub struct FieldFilter {
+  field: String,
+  matcher: FieldMatcher,
+}
+
+enum FieldMatcher {
+  Equals(String),
+  Regex(Regex),
+}
+
+impl FieldFilter {
+  pub fn matches(&self, record: &Record) -> bool {
+    match (record.field(&self.field), &self.matcher) {
+      (None, _) => false,
+      (Some(value), FieldMatcher::Equals(expected)) => value == expected,
+      (Some(value), FieldMatcher::Regex(re)) => re.is_match(value).unwrap_or(false),
+    }
+  }
+}
+
+impl FromStr for FieldFilter {
+  type Err = ConfigError;
+
+  fn from_str(s: &str) -> Result<FieldFilter, ConfigError> {
+    let index = s.find(['=', '~']).ok_or(ConfigError::FilterInvalid)?;
+    let (field, value) = (s[..index].trim(), &s[index + 1..]);
+    if field.is_empty() {
+      return Err(ConfigError::FilterInvalid);
+    }
+
+    let matcher = if s[index..].starts_with('=') {
+      FieldMatcher::Equals(value.to_owned())
+    } else {
+      FieldMatcher::Regex(Regex::new(value).map_err(|_| ConfigError::FilterInvalid)?)
+    };
+    Ok(FieldFilter { field: field.to_owned(), matcher })
+  }
+}
+
+/// The fields of a structured line. Nested objects are flattened, their
+/// fields are named `outer.inner`. The line written by the application is
+/// the `log` field of container logs.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Record {
+  fields: BTreeMap<String, String>,
+  /// the line of a container log is continued by the next record
+  partial: bool,
+}
+
+impl Record {
+  /// Returns `None` for plain lines and for lines which don't have the
+  /// structure of the format.
+  pub fn parse(format: LineFormat, line: &str) -> Option<Record> {
+    match format {
+      LineFormat::Plain => None,
+      LineFormat::Json => {
+        let object = match serde_json::from_str(line) {
+          Ok(Value::Object(object)) => object,
+          _ => return None,
+        };
+        let mut record = Record::default();
+        record.add_object("", object);
+        Some(record)
+      },
+      LineFormat::Logfmt => parse_logfmt(line).map(|fields| Record { fields, partial: false }),
+      LineFormat::Docker => {
+        let mut record = Record::parse(LineFormat::Json, line)?;
+        let log = record.fields.get_mut("log")?;
+        // only the last part of a split line ends with a newline
+        match log.pop() {
+          Some('\n') => {},
+          Some(c) => {
+            log.push(c);
+            record.partial = true;
+          },
+          None => record.partial = true,
+        }
+        Some(record)
+      },
+      LineFormat::Cri => {
+        let mut parts = line.splitn(4, ' ');
+        let (time, stream, tag) = (parts.next()?, parts.next()?, parts.next()?);
+        if stream != "stdout" && stream != "stderr" {
+          return None;
+        }
+        let partial = match tag.split(':').next() {
+          Some("P") => true,
+          Some("F") => false,
+          _ => return None,
+        };
+
+        let mut fields = BTreeMap::new();
+        fields.insert("time".to_owned(), time.to_owned());
+        fields.insert("stream".to_owned(), stream.to_owned());
+        fields.insert("log".to_owned(), parts.next().unwrap_or("").to_owned());
+        Some(Record { fields, partial })
+      },
+      LineFormat::Syslog => parse_syslog(line).map(|fields| Record { fields, partial: false }),
+      // entries of the export format span several lines, see `parse_journal`
+      LineFormat::Journal => Record::parse(LineFormat::Json, line),
+      LineFormat::Access => parse_access(line).map(|fields| Record { fields, partial: false }),
+    }
+  }
+
+  pub fn is_partial(&self) -> bool {
+    self.partial
+  }
+
+  /// Adds a partial record which precedes this one to the start of the
+  /// container log line.
+  pub fn prepend(&mut self, part: &Record) {
+    let log = format!("{}{}", part.field("log").unwrap_or(""), self.field("log").unwrap_or(""));
+    self.fields.insert("log".to_owned(), log);
+  }
+
+  fn add_object(&mut self, prefix: &str, object: serde_json::Map<String, Value>) {
+    for (key, value) in object {
+      let key = format!("{}{}", prefix, key);
+      match value {
+        Value::Object(inner) => self.add_object(&format!("{}.", key), inner),
+        Value::String(s) => { self.fields.insert(key, s); },
+        // the journal writes fields which are no valid utf-8 as arrays of bytes
+        Value::Array(ref items) if !items.is_empty() && items.iter().all(|item| item.as_u64().is_some_and(|b| b < 256)) => {
+          let bytes: Vec<u8> = items.iter().filter_map(|item| item.as_u64()).map(|b| b as u8).collect();
+          self.fields.insert(key, String::from_utf8_lossy(&bytes).into_owned());
+        },
+        other => { self.fields.insert(key, other.to_string()); },
+      }
+    }
+  }
+
+  pub fn field(&self, name: &str) -> Option<&str> {
+    self.fields.get(name).map(|value| value.as_str())
+  }
+
+  /// The timestamp of the record, adjusted to local time like the
+  /// timestamps of plain lines.
+  pub fn timestamp(&self, conf: &Config) -> Option<u64> {
+    match conf.time_field {
+      Some(ref name) => self.field(name).and_then(|value| parse_timestamp(value, conf)),
+      None => DEFAULT_TIME_FIELDS.iter()
+        .filter_map(|name| self.field(name))
+        .filter_map(|value| parse_timestamp(value, conf))
+        .next(),
+    }
+  }
+
+  /// The http status of a request in an access log.
+  pub fn status(&self) -> Option<u16> {
+    self.field("status").and_then(|status| status.parse().ok())
+  }
+
+  /// The response time of a request in an access log in milliseconds. A
+  /// decimal number is in seconds (nginx' `$request_time`), an integer in
+  /// microseconds (apache's `%D`).
+  pub fn response_time_ms(&self) -> Option<u64> {
+    let response_time = self.field("response_time")?;
+    if response_time.contains('.') {
+      response_time.parse::<f64>().ok().map(|secs| (secs * 1000.0).round() as u64)
+    } else {
+      response_time.parse::<u64>().ok().map(|micros| micros / 1000)
+    }
+  }
+
+  /// All filters and the severity need to match.
+  pub fn passes_filters(&self, conf: &Config) -> bool {
+    if !conf.filters.iter().all(|filter| filter.matches(self)) {
+      return false;
+    }
+    match conf.max_severity {
+      None => true,
+      // records without severity can't be told apart and never match
+      Some(max_severity) => self.field("severity").or_else(|| self.field("PRIORITY"))
+        .and_then(severity_code)
+        .is_some_and(|severity| severity <= max_severity),
+    }
+  }
+
+  /// All filters need to match. The pattern is matched against the
+  /// configured fields, or against the whole line if there are none.
+  /// Returns the text which matched.
+  pub fn find_match<'a>(&'a self, conf: &Config, line: &'a str) -> Option<&'a str> {
+    if !self.passes_filters(conf) {
+      return None;
+    }
+
+    if conf.match_fields.is_empty() {
+      return Some(line).filter(|line| conf.re.is_match(line).unwrap());
+    }
+    conf.match_fields.iter()
+      .filter_map(|name| self.field(name))
+      .find(|value| conf.re.is_match(value).unwrap())
+  }
+}
+
+/// Splits a line like `ts=2018-09-13T00:03:01Z level=error msg="db timeout"`.
+/// Keys without value get an empty value. Returns `None` if the line has no
+/// `key=value` pair or an unterminated quote.
+fn parse_logfmt(line: &str) -> Option<BTreeMap<String, String>> {
+  let mut fields = BTreeMap::new();
+  let mut has_pair = false;
+  let mut chars = line.chars().peekable();
+
+  loop {
+    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+      chars.next();
+    }
+    if chars.peek().is_none() {
+      break;
+    }
+
+    let mut key = String::new();
+    while let Some(&c) = chars.peek() {
+      if c.is_whitespace() || c == '=' {
+        break;
+      }
+      key.push(c);
+      chars.next();
+    }
+
+    let mut value = String::new();
+    if chars.peek() == Some(&'=') {
+      chars.next();
+      has_pair = true;
+      if chars.peek() == Some(&'"') {
+        chars.next();
+        loop {
+          match chars.next()? {
+            '"' => break,
+            '\\' => match chars.next()? {
+              'n' => value.push('\n'),
+              't' => value.push('\t'),
+              escaped => value.push(escaped),
+            },
+            c => value.push(c),
+          }
+        }
+      } else {
+        while let Some(&c) = chars.peek() {
+          if c.is_whitespace() {
+            break;
+          }
+          value.push(c);
+          chars.next();
+        }
+      }
+    }
+
+    if key.is_empty() {
+      return None;
+    }
+    fields.insert(key, value);
+  }
+
+  if has_pair { Some(fields) } else { None }
+}
+
+/// Parses the entries of the journal, either in the export format or as
+/// json lines. The entries are returned in the order of the input.
+pub fn parse_journal(bytes: &[u8]) -> Vec<Record> {
+  let start = bytes.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(bytes.len());
+  if bytes[start..].starts_with(b"{") {
+    return bytes.split(|&b| b == b'\n')
+      .filter_map(|line| Record::parse(LineFormat::Json, String::from_utf8_lossy(line).trim()))
+      .collect();
+  }
+
+  // entries are separated by an empty line, fields are `KEY=value` lines.
+  // fields with binary data are `KEY\n`, a little endian 64 bit length, the
+  // data and `\n`.
+  let mut records = Vec::new();
+  let mut record = Record::default();
+  let mut pos = 0;
+  while pos < bytes.len() {
+    let line_end = bytes[pos..].iter().position(|&b| b == b'\n').map_or(bytes.len(), |i| pos + i);
+    let line = &bytes[pos..line_end];
+    pos = line_end + 1;
+
+    if line.is_empty() {
+      if !record.fields.is_empty() {
+        records.push(std::mem::take(&mut record));
+      }
+      continue;
+    }
+
+    let (key, value) = match line.iter().position(|&b| b == b'=') {
+      Some(index) => (&line[..index], &line[index + 1..]),
+      None => {
+        let len_bytes = match bytes.get(pos..pos + 8) {
+          Some(len_bytes) => len_bytes,
+          None => break,
+        };
+        let mut len = [0; 8];
+        len.copy_from_slice(len_bytes);
+        let start = pos + 8;
+        let end = start.saturating_add(u64::from_le_bytes(len) as usize);
+        match bytes.get(start..end) {
+          Some(value) => {
+            pos = end + 1;
+            (line, value)
+          },
+          None => break,
+        }
+      },
+    };
+    record.fields.insert(String::from_utf8_lossy(key).into_owned(), String::from_utf8_lossy(value).into_owned());
+  }
+  if !record.fields.is_empty() {
+    records.push(record);
+  }
+
+  records
+}
+
+/// Returns the numerical code of a syslog severity, given by name or code.
+/// Lower codes are more severe.
+pub fn severity_code(severity: &str) -> Option<u8> {
+  let severity = severity.to_lowercase();
+  let name = match severity.as_str() {
+    "emergency" | "panic" => "emerg",
+    "critical" => "crit",
+    "error" => "err",
+    "warn" => "warning",
+    "informational" => "info",
+    name => name,
+  };
+  match SEVERITIES.iter().position(|&s| s == name) {
+    Some(code) => Some(code as u8),
+    None => name.parse().ok().filter(|&code| code < 8),
+  }
+}
+
+/// Splits off the next whitespace separated token of `rest`.
+fn next_token<'a>(rest: &mut &'a str) -> Option<&'a str> {
+  let trimmed = rest.trim_start();
+  if trimmed.is_empty() {
+    return None;
+  }
+  let end = trimmed.find(char::is_whitespace).unwrap_or(trimmed.len());
+  *rest = &trimmed[end..];
+  Some(&trimmed[..end])
+}
+
+/// Extracts pri, facility, severity, time, host, app, pid, msgid,
+/// structured_data and message of RFC 5424 lines and of RFC 3164 lines
+/// like `Sep 13 00:03:01 host sshd[123]: message`. The priority is often
+/// missing in files, rsyslog may write RFC 3339 timestamps instead.
+fn parse_syslog(line: &str) -> Option<BTreeMap<String, String>> {
+  let mut fields = BTreeMap::new();
+  let mut rest = line;
+
+  if rest.starts_with('<') {
+    let end = rest.find('>')?;
+    let pri: u8 = rest[1..end].parse().ok().filter(|&pri| pri < 192)?;
+    fields.insert("pri".to_owned(), pri.to_string());
+    fields.insert("facility".to_owned(), (pri / 8).to_string());
+    fields.insert("severity".to_owned(), SEVERITIES[(pri % 8) as usize].to_owned());
+    rest = &rest[end + 1..];
+  }
+
+  if let Some(rfc5424) = rest.strip_prefix("1 ") {
+    rest = rfc5424;
+    for &name in &["time", "host", "app", "pid", "msgid"] {
+      let value = next_token(&mut rest)?;
+      // `-` is the nil value
+      if value != "-" {
+        fields.insert(name.to_owned(), value.to_owned());
+      }
+    }
+
+    rest = rest.trim_start();
+    if let Some(without_nil) = rest.strip_prefix('-') {
+      rest = without_nil;
+    } else if rest.starts_with('[') {
+      let end = structured_data_end(rest)?;
+      fields.insert("structured_data".to_owned(), rest[..end].to_owned());
+      rest = &rest[end..];
+    } else {
+      return None;
+    }
+    let message = rest.strip_prefix(' ').unwrap_or(rest);
+    fields.insert("message".to_owned(), message.trim_start_matches('\u{feff}').to_owned());
+    return Some(fields);
+  }
+
+  let first = next_token(&mut rest)?;
+  if DateTime::parse_from_rfc3339(first).is_ok() {
+    fields.insert("time".to_owned(), first.to_owned());
+  } else {
+    // `Sep 13 00:03:01`, the day is padded with a space
+    let is_month = first.len() == 3 && first.chars().all(|c| c.is_ascii_alphabetic());
+    let (day, time) = (next_token(&mut rest)?, next_token(&mut rest)?);
+    if !is_month || !time.contains(':') {
+      return None;
+    }
+    fields.insert("time".to_owned(), format!("{} {} {}", first, day, time));
+  }
+  fields.insert("host".to_owned(), next_token(&mut rest)?.to_owned());
+
+  // the tag `app[pid]:` or `app:` is optional
+  let mut message = rest.trim_start();
+  let mut tag_rest = message;
+  if let Some(tag) = next_token(&mut tag_rest).and_then(|tag| tag.strip_suffix(':')) {
+    match tag.find('[') {
+      Some(index) if tag.ends_with(']') => {
+        fields.insert("app".to_owned(), tag[..index].to_owned());
+        fields.insert("pid".to_owned(), tag[index + 1..tag.len() - 1].to_owned());
+      },
+      _ => { fields.insert("app".to_owned(), tag.to_owned()); },
+    }
+    message = tag_rest.trim_start();
+  }
+  fields.insert("message".to_owned(), message.to_owned());
+  Some(fields)
+}
+
+/// Splits off the quoted string at the start of `rest`, `\"` is an escaped
+/// quote.
+fn next_quoted<'a>(rest: &mut &'a str) -> Option<&'a str> {
+  let trimmed = rest.trim_start().strip_prefix('"')?;
+  let mut escaped = false;
+  for (i, c) in trimmed.char_indices() {
+    match c {
+      _ if escaped => escaped = false,
+      '\\' => escaped = true,
+      '"' => {
+        *rest = &trimmed[i + 1..];
+        return Some(&trimmed[..i]);
+      },
+      _ => {},
+    }
+  }
+  None
+}
+
+/// Extracts host, user, time, method, path, protocol, status, bytes,
+/// referer, user_agent and response_time of a line like
+/// `127.0.0.1 - frank [13/Sep/2018:00:03:01 +0200] "GET /a HTTP/1.1" 200 2326 "-" "curl/7.61.0" 0.012`.
+/// Referer, user agent and response time are optional.
+fn parse_access(line: &str) -> Option<BTreeMap<String, String>> {
+  let mut fields = BTreeMap::new();
+  let mut rest = line;
+
+  fields.insert("host".to_owned(), next_token(&mut rest)?.to_owned());
+  next_token(&mut rest)?;
+  let user = next_token(&mut rest)?;
+  if user != "-" {
+    fields.insert("user".to_owned(), user.to_owned());
+  }
+
+  let bracketed = rest.trim_start().strip_prefix('[')?;
+  let end = bracketed.find(']')?;
+  fields.insert("time".to_owned(), bracketed[..end].to_owned());
+  rest = &bracketed[end + 1..];
+
+  let mut request = next_quoted(&mut rest)?.splitn(3, ' ');
+  for &name in &["method", "path", "protocol"] {
+    if let Some(value) = request.next() {
+      fields.insert(name.to_owned(), value.to_owned());
+    }
+  }
+
+  let status = next_token(&mut rest)?;
+  status.parse::<u16>().ok()?;
+  fields.insert("status".to_owned(), status.to_owned());
+  fields.insert("bytes".to_owned(), next_token(&mut rest)?.to_owned());
+
+  for &name in &["referer", "user_agent"] {
+    match next_quoted(&mut rest) {
+      Some(value) => { fields.insert(name.to_owned(), value.to_owned()); },
+      None => break,
+    }
+  }
+  if let Some(response_time) = next_token(&mut rest).filter(|t| t.parse::<f64>().is_ok()) {
+    fields.insert("response_time".to_owned(), response_time.to_owned());
+  }
+
+  Some(fields)
+}
+
+/// Returns the index after the structured data elements at the start of
+/// `s`, e.g. `[id key="value"][id2 key="\]"]`.
+fn structured_data_end(s: &str) -> Option<usize> {
+  let mut in_element = false;
+  let mut in_quotes = false;
+  let mut escaped = false;
+  for (i, c) in s.char_indices() {
+    match c {
+      _ if escaped => escaped = false,
+      '\\' if in_quotes => escaped = true,
+      '"' if in_element => in_quotes = !in_quotes,
+      '[' if !in_element => in_element = true,
+      ']' if in_element && !in_quotes => {
+        in_element = false;
+        if !s[i + 1..].starts_with('[') {
+          return Some(i + 1);
+        }
+      },
+      _ if !in_element => return None,
+      _ => {},
+    }
+  }
+  None
+}
+
+/// Parses unix timestamps in seconds or milliseconds, RFC 3339 dates and
+/// dates in one of the configured date formats.
+fn parse_timestamp(value: &str, conf: &Config) -> Option<u64> {
+  if let Ok(secs) = value.parse::<f64>() {
+    // timestamps in milli-, micro- or nanoseconds are larger than any
+    // plausible timestamp in seconds
+    let mut secs = secs;
+    while secs > 1e11 {
+      secs /= 1000.0;
+    }
+    return Some(utils::get_local_ts_from_utc(secs as i64));
+  }
+
+  if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+    return Some(utils::get_local_ts_from_utc(date.timestamp()));
+  }
+  // the time of access logs
+  if let Ok(date) = DateTime::parse_from_str(value, "%d/%b/%Y:%H:%M:%S %z") {
+    return Some(utils::get_local_ts_from_utc(date.timestamp()));
+  }
+
+  // dates without offset are local time, like the dates of plain lines
+  if let Ok(date) = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f") {
+    return Some(date.timestamp() as u64);
+  }
+  // the timestamp of BSD syslog lines
+  let syslog = if conf.line_format == LineFormat::Syslog { Some("%b %d %H:%M:%S") } else { None };
+  conf.date_formats.iter()
+    .map(|format| format.pattern.as_str())
+    .chain(syslog)
+    .filter_map(|pattern| utils::parse_date(&conf.locale.to_english(value), pattern))
+    .map(utils::get_timestamp)
+    .next()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn should_flatten_json_objects() {
+    // given
+    let line = r#"{"ts":"2018-09-13T00:03:01Z","level":"error","http":{"status":500},"tags":["a"]}"#;
+
+    // when
+    let record = Record::parse(LineFormat::Json, line).unwrap();
+
+    // then
+    assert_eq!(record.field("level"), Some("error"));
+    assert_eq!(record.field("http.status"), Some("500"));
+    assert_eq!(record.field("tags"), Some(r#"["a"]"#));
+    assert_eq!(Record::parse(LineFormat::Json, "2018-09-13 00:03:01 foo"), None);
+  }
+
+  #[test]
+  fn should_parse_logfmt() {
+    // given
+    let line = r#"ts=2018-09-13T00:03:01Z level=error msg="db \"main\" timeout" retry"#;
+
+    // when
+    let record = Record::parse(LineFormat::Logfmt, line).unwrap();
+
+    // then
+    assert_eq!(record.field("ts"), Some("2018-09-13T00:03:01Z"));
+    assert_eq!(record.field("level"), Some("error"));
+    assert_eq!(record.field("msg"), Some(r#"db "main" timeout"#));
+    assert_eq!(record.field("retry"), Some(""));
+    assert_eq!(Record::parse(LineFormat::Logfmt, "2018-09-13 00:03:01 foo"), None);
+    assert_eq!(Record::parse(LineFormat::Logfmt, r#"msg="unterminated"#), None);
+  }
+
+  #[test]
+  fn should_unwrap_container_logs() {
+    // given
+    let docker = r#"{"log":"db timeout\n","stream":"stderr","time":"2018-09-13T00:03:01.123456789Z"}"#;
+    let docker_partial = r#"{"log":"db ","stream":"stderr","time":"2018-09-13T00:03:01.123456789Z"}"#;
+    let cri = "2018-09-13T00:03:01.123456789Z stdout F db timeout";
+    let cri_partial = "2018-09-13T00:03:01.123456789Z stdout P db ";
+
+    // when
+    let docker = Record::parse(LineFormat::Docker, docker).unwrap();
+    let docker_partial = Record::parse(LineFormat::Docker, docker_partial).unwrap();
+    let cri = Record::parse(LineFormat::Cri, cri).unwrap();
+    let cri_partial = Record::parse(LineFormat::Cri, cri_partial).unwrap();
+
+    // then
+    assert_eq!((docker.field("log"), docker.is_partial()), (Some("db timeout"), false));
+    assert_eq!((docker_partial.field("log"), docker_partial.is_partial()), (Some("db "), true));
+    assert_eq!((cri.field("log"), cri.field("stream"), cri.is_partial()), (Some("db timeout"), Some("stdout"), false));
+    assert_eq!((cri_partial.field("log"), cri_partial.is_partial()), (Some("db "), true));
+    assert_eq!(Record::parse(LineFormat::Cri, "2018-09-13 00:03:01 foo"), None);
+  }
+
+  #[test]
+  fn should_parse_syslog() {
+    // given
+    let rfc5424 = r#"<165>1 2018-09-13T00:03:01.003Z mymachine evntslog - ID47 [exampleSDID@32473 iut="3" eventSource="Appl\]"] An application event"#;
+    let rfc3164 = "Sep  3 00:03:01 myhost sshd[1234]: Failed password for root";
+    let without_tag = "Sep 13 00:03:01 myhost -- MARK --";
+
+    // when
+    let rfc5424 = Record::parse(LineFormat::Syslog, rfc5424).unwrap();
+    let rfc3164 = Record::parse(LineFormat::Syslog, rfc3164).unwrap();
+    let without_tag = Record::parse(LineFormat::Syslog, without_tag).unwrap();
+
+    // then
+    assert_eq!(rfc5424.field("severity"), Some("notice"));
+    assert_eq!(rfc5424.field("facility"), Some("20"));
+    assert_eq!(rfc5424.field("time"), Some("2018-09-13T00:03:01.003Z"));
+    assert_eq!(rfc5424.field("host"), Some("mymachine"));
+    assert_eq!(rfc5424.field("app"), Some("evntslog"));
+    assert_eq!(rfc5424.field("pid"), None);
+    assert_eq!(rfc5424.field("msgid"), Some("ID47"));
+    assert_eq!(rfc5424.field("message"), Some("An application event"));
+    assert_eq!(rfc3164.field("time"), Some("Sep 3 00:03:01"));
+    assert_eq!(rfc3164.field("app"), Some("sshd"));
+    assert_eq!(rfc3164.field("pid"), Some("1234"));
+    assert_eq!(rfc3164.field("message"), Some("Failed password for root"));
+    assert_eq!(rfc3164.field("severity"), None);
+    assert_eq!(without_tag.field("app"), None);
+    assert_eq!(without_tag.field("message"), Some("-- MARK --"));
+    assert_eq!(Record::parse(LineFormat::Syslog, "2018-09-13 00:03:01 foo"), None);
+    assert_eq!(severity_code("error"), Some(3));
+    assert_eq!(severity_code("4"), Some(4));
+  }
+
+  #[test]
+  fn should_parse_syslog_dates_only_in_syslog_lines() {
+    // given
+    let mut conf = Config::new(1, "foo".to_owned(), "app.log".to_owned(), 1, 1, "".to_owned(), 0, false, false).unwrap();
+    let syslog = Record::parse(LineFormat::Syslog, "Sep  3 00:03:01 myhost sshd[1234]: Failed password").unwrap();
+    let json = Record::parse(LineFormat::Json, r#"{"time":"Sep 3 00:03:01","msg":"Failed password"}"#).unwrap();
+
+    // when
+    conf.line_format = LineFormat::Syslog;
+    let syslog_ts = syslog.timestamp(&conf);
+    conf.line_format = LineFormat::Json;
+    let json_ts = json.timestamp(&conf);
+
+    // then
+    assert!(syslog_ts.is_some());
+    assert_eq!(json_ts, None);
+  }
+
+  #[test]
+  fn should_parse_journal_export_format() {
+    // given
+    let mut export = b"__REALTIME_TIMESTAMP=1536796981000000\nMESSAGE=first\n_SYSTEMD_UNIT=nginx.service\n\n".to_vec();
+    export.extend_from_slice(b"__REALTIME_TIMESTAMP=1536796982000000\nMESSAGE\n");
+    export.extend_from_slice(&8u64.to_le_bytes());
+    export.extend_from_slice(b"two\nline\nPRIORITY=3\n");
+    let json = br#"{"__REALTIME_TIMESTAMP":"1536796981000000","MESSAGE":[98,105,110],"PRIORITY":"6"}"#;
+
+    // when
+    let records = parse_journal(&export);
+    let json_records = parse_journal(json);
+
+    // then
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].field("MESSAGE"), Some("first"));
+    assert_eq!(records[0].field("_SYSTEMD_UNIT"), Some("nginx.service"));
+    assert_eq!(records[1].field("MESSAGE"), Some("two\nline"));
+    assert_eq!(records[1].field("PRIORITY"), Some("3"));
+    assert_eq!(json_records.len(), 1);
+    assert_eq!(json_records[0].field("MESSAGE"), Some("bin"));
+  }
+
+  #[test]
+  fn should_parse_access_logs() {
+    // given
+    let combined = r#"127.0.0.1 - frank [13/Sep/2018:00:03:01 +0200] "GET /a?q=\"x\" HTTP/1.1" 503 2326 "-" "curl/7.61.0" 0.512"#;
+    let common = r#"::1 - - [13/Sep/2018:00:03:01 +0000] "GET / HTTP/1.0" 200 -"#;
+    let apache_micros = r#"::1 - - [13/Sep/2018:00:03:01 +0000] "GET / HTTP/1.0" 200 12 "-" "-" 1500"#;
+
+    // when
+    let combined = Record::parse(LineFormat::Access, combined).unwrap();
+    let common = Record::parse(LineFormat::Access, common).unwrap();
+    let apache_micros = Record::parse(LineFormat::Access, apache_micros).unwrap();
+
+    // then
+    assert_eq!(combined.field("user"), Some("frank"));
+    assert_eq!(combined.field("time"), Some("13/Sep/2018:00:03:01 +0200"));
+    assert_eq!(combined.field("method"), Some("GET"));
+    assert_eq!(combined.field("path"), Some(r#"/a?q=\"x\""#));
+    assert_eq!(combined.field("user_agent"), Some("curl/7.61.0"));
+    assert_eq!(combined.status(), Some(503));
+    assert_eq!(combined.response_time_ms(), Some(512));
+    assert_eq!(common.field("user"), None);
+    assert_eq!(common.status(), Some(200));
+    assert_eq!(common.response_time_ms(), None);
+    assert_eq!(apache_micros.response_time_ms(), Some(1));
+    assert_eq!(Record::parse(LineFormat::Access, "2018-09-13 00:03:01 foo"), None);
+  }
+
+  #[test]
+  fn should_filter_fields() {
+    // given
+    let record = Record::parse(LineFormat::Json, r#"{"level":"error","msg":"connection timeout"}"#).unwrap();
+
+    // when
+    let equals: FieldFilter = "level=error".parse().unwrap();
+    let regex: FieldFilter = "msg~time(out)?$".parse().unwrap();
+    let missing: FieldFilter = "user=root".parse().unwrap();
+
+    // then
+    assert!(equals.matches(&record));
+    assert!(regex.matches(&record));
+    assert!(!missing.matches(&record));
+    assert!("level".parse::<FieldFilter>().is_err());
+  }
+}
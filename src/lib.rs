@@ -32,27 +32,52 @@
 //! ```
 
 extern crate chrono;
+extern crate fancy_regex;
 extern crate glob;
 extern crate memmap;
+extern crate serde_json;
 extern crate time;
 
 pub use config::*;
+pub use encoding::Encoding;
+pub use format::{FieldFilter, LineFormat};
+pub use state::State;
+pub use summary::*;
 use chrono::prelude::*;
-use glob::glob;
+use format::Record;
 use memmap::Mmap;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use source::{LineSource, ReverseReader, SliceLines};
 use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 use std::str;
 use std::time::SystemTime;
 
 mod config;
+mod discovery;
+mod distinct;
+mod encoding;
+mod format;
+mod locale;
+mod source;
+mod state;
+mod summary;
 mod utils;
 
+/// pipes and files in /proc are read completely, up to this many bytes
+const MAX_UNSIZED_LEN: u64 = 64 * 1024 * 1024;
+
 #[derive(Debug, Clone, PartialEq, Eq, Copy)]
 enum SearchError {
   NotFile,
   EmptyFile,
   NotUtf8,
   TimestampTooOld,
+  Truncated,
+  Io(io::ErrorKind),
 }
 
 impl From<SearchError> for String {
@@ -62,28 +87,67 @@ impl From<SearchError> for String {
       SearchError::EmptyFile => "file empty".to_owned(),
       SearchError::NotUtf8 => "file not utf8".to_owned(),
       SearchError::TimestampTooOld => "timestamp in line too old".to_owned(),
+      SearchError::Truncated => "file truncated during the search".to_owned(),
+      SearchError::Io(kind) => kind.to_string(),
     }
   }
 }
 
-pub fn run(conf: &Config) -> Result<(u64, u64), String> {
-  let mut files_matched = 0;
-  let mut exp = conf.logfile.to_owned();
-  let star = String::from("*");
-  exp.push_str(&star);
+/// State which is carried from one line of a file to the next one.
+#[derive(Default)]
+struct SearchState {
+  /// index into `conf.date_formats` of the format which could be parsed
+  /// from the last line. it is tried first on the next line.
+  last_format: usize,
+  /// lines without a timestamp which were found after the current line,
+  /// in reverse order. only used in multiline mode.
+  continuation: Vec<String>,
+  /// the newest container log record whose partial records which precede
+  /// it were not read yet
+  pending: Option<Record>,
+  counts: FileSummary,
+}
 
-  let mut matches = 0;
+/// Timestamps, adjusted to local time, between which lines are counted.
+#[derive(Debug, Clone, Copy)]
+struct Window {
+  oldest_ts: u64,
+  /// start of the current interval. lines between `oldest_ts` and this
+  /// belong to the previous interval, which is only searched for trends.
+  current_ts: u64,
+  /// lines newer than this are counted as lines from the future
+  newest_ts: u64,
+  now_ts: u64,
+}
 
-  let pattern_spaces: Vec<&str> = conf.date_pattern.split_whitespace().collect();
-  let whitespaces_in_date = pattern_spaces.len();
-  
-  if conf.debug {
-    println!("looking for files matching {}", exp);
-  }
+/// The interval a line belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Period {
+  Skipped,
+  Previous,
+  Current,
+}
+
+/// Searches the log files and returns the number of matches and of
+/// searched files. `run_summary` returns all counts.
+pub fn run(conf: &Config) -> Result<(u64, u64), String> {
+  run_summary(conf).map(|summary| (summary.matches, summary.files_matched))
+}
+
+/// Searches the log files and returns the counts which are needed for the
+/// thresholds and alerts.
+pub fn run_summary(conf: &Config) -> Result<Summary, String> {
+  let mut summary = Summary::default();
 
   // the timestamp is adjusted to local time
   let now = SystemTime::now();
-  let oldest_ts = utils::get_oldest_allowed_local_ts(conf, now);
+  let current_ts = utils::get_oldest_allowed_local_ts(conf, now);
+  let window = Window {
+    oldest_ts: current_ts.saturating_sub((conf.lookback_minutes() - conf.interval_to_check) * 60),
+    current_ts,
+    newest_ts: utils::get_newest_allowed_local_ts(conf, now),
+    now_ts: utils::get_now_local_ts(now),
+  };
 
   if conf.debug {
     let oldest_date_no_tz_offset = NaiveDateTime::from_timestamp(utils::get_oldest_allowed_utc_ts(conf, now) as i64, 0);
@@ -91,113 +155,317 @@ pub fn run(conf: &Config) -> Result<(u64, u64), String> {
     println!("oldest allowed date in utc: {} and with tz offset: {}", oldest_date_no_tz_offset, adjusted_date);
   }
   
-  // for all files that match pattern
-  for entry in glob(&exp).expect("failed to read glob pattern") {
-    match entry {
-      Ok(path) => {
-        let p = path.to_str().expect("path not available");
-
-        if !utils::check_file_age(&conf, p) {
-          files_matched += 1;
+  conf.validate()?;
+
+  if conf.stdin {
+    let mut bytes = Vec::new();
+    let (file_summary, err) = match io::stdin().read_to_end(&mut bytes) {
+      Ok(_) => search_journal(&bytes, conf, window),
+      Err(e) => (FileSummary::default(), Some(SearchError::Io(e.kind()))),
+    };
+    summary.files_matched += 1;
+    if let Some(SearchError::Io(kind)) = err {
+      summary.failed_files.push(("-".to_owned(), kind.to_string()));
+    }
+    summary.add_file("-", &file_summary);
+  }
+
+  // a file which is reached through several log files or links is only
+  // searched once
+  let mut found = HashSet::new();
+  for logfile in &conf.logfiles {
+    let mut files = discovery::find_files(conf, logfile)?;
+    files.retain(|path| is_new_file(conf, &mut found, path));
+    // a glob may match several log files, each one is searched together
+    // with its own rotated versions only
+    let live = discovery::find_live_files(logfile)?;
+    for mut chain in discovery::rotation_chains(conf, files, &live) {
+      discovery::sort_newest_first(&mut chain);
+      search_rotated_files(conf, &chain, &live, window, &mut summary);
+    }
+  }
+
+  // files in log directories are unrelated to each other, so each one is
+  // searched on its own.
+  for logdir in &conf.logdirs {
+    for path in discovery::walk_dir(conf, logdir) {
+      if is_new_file(conf, &mut found, &path) {
+        let files = [path];
+        search_rotated_files(conf, &files, &files, window, &mut summary);
+      }
+    }
+  }
+
+  Ok(summary)
+}
+
+/// Returns false if the file was already found before, e.g. through
+/// a symbolic or hard link.
+fn is_new_file(conf: &Config, found: &mut HashSet<discovery::FileId>, path: &Path) -> bool {
+  match discovery::file_id(path) {
+    // the error is reported when the file is searched
+    None => true,
+    Some(id) => {
+      let is_new = found.insert(id);
+      if !is_new && conf.debug {
+        println!("skipping {:?} because the same file was already found", path);
+      }
+      is_new
+    },
+  }
+}
+
+/// Searches a log file and its rotated versions, `files` needs to be
+/// sorted newest first. `live` are the files which are still written to,
+/// in contrast to the rotated versions.
+fn search_rotated_files(conf: &Config, files: &[PathBuf], live: &[PathBuf], window: Window, summary: &mut Summary) {
+  // the files are searched newest first. once a file contains lines which
+  // are too old, all remaining files are older and don't need to be opened.
+  let mut reached_old_files = false;
+
+  // for all files that belong to the log file
+  for path in files {
+    let p = match path.to_str() {
+      Some(p) => p,
+      None => {
+        // e.g. a file below a -logdir whose name is not utf-8
+        if conf.debug {
+          println!("ERROR the path {:?} is not valid utf-8", path);
+        }
+        summary.files_matched += 1;
+        summary.failed_files.push((path.to_string_lossy().into_owned(), "path is not valid utf-8".to_owned()));
+        continue;
+      },
+    };
+
+    // the age of files after the cutoff isn't checked, so errors while
+    // reading their metadata aren't reported
+    if !reached_old_files {
+      reached_old_files = match utils::get_file_age(conf, p) {
+        Ok(file_age) => {
+          summary.newest_file_age = Some(summary.newest_file_age.map_or(file_age, |age| age.min(file_age)));
+          // check if the file age is >= now - interval_to_check
+          file_age > conf.lookback_minutes() * 60
+        },
+        Err(e) => {
           if conf.debug {
-            println!("skipping {:?} because too old", conf.logfile);
+            println!("ERROR while checking the age of the file {}: {}", p, e);
           }
-          continue; 
+          summary.files_matched += 1;
+          summary.failed_files.push((p.to_owned(), e.kind().to_string()));
+          continue;
+        }
+      };
+    }
+
+    if reached_old_files {
+      summary.files_matched += 1;
+      if conf.debug {
+        println!("skipping {:?} because too old", p);
+      }
+      continue; 
+    }
+
+    let (file_summary, err) = search_file(p, conf, window, live.contains(path));
+    if let Some(ts) = file_summary.newest_ts {
+      let line_age = window.now_ts.saturating_sub(ts);
+      summary.newest_line_age = Some(summary.newest_line_age.map_or(line_age, |age| age.min(line_age)));
+    }
+
+    match err {
+      None => {
+        summary.files_matched += 1;
+        summary.add_file(p, &file_summary);
+      },
+      Some(err) => {
+        // an error can occur because e.g. the file is empty, not utf8 or
+        // because the timestamp of the line is too old. so we can
+        // just stop searching further and add the matches found so far.
+        if conf.debug {
+          let err: String = err.into();
+          println!("ERROR while searching the file {}: {}
+                    There were {} matches until the error appeared.", p, err, file_summary.matches);
         }
 
-        let local_matches = search_file(p, &conf, whitespaces_in_date, oldest_ts);
-        match local_matches {
-          Ok(matches_in_file) => {
-            files_matched += 1;
-            matches += matches_in_file;
+        match err {
+          SearchError::TimestampTooOld => {
+            summary.files_matched += 1;
+            reached_old_files = true;
           },
-          Err((err, matches_in_file)) => {
-            // an error can occur because e.g. the file is empty, not utf8 or
-            // because the timestamp of the line is too old. so we can
-            // just stop searching further and add the matches found so far.
-            if conf.debug {
-              let err: String = err.into();
-              println!("ERROR while searching the file {}: {}
-                        There were {} matches until the error appeared.", p, err, matches);
-            }
-
-            match err {
-              SearchError::TimestampTooOld => files_matched += 1,
-              _ => {},
-            }
-
-            matches += matches_in_file;
-            continue;
-          }
+          SearchError::Truncated => {
+            summary.files_matched += 1;
+          },
+          SearchError::Io(kind) => {
+            summary.files_matched += 1;
+            summary.failed_files.push((p.to_owned(), kind.to_string()));
+          },
+          _ => {},
         }
-      },
-      Err(e) => println!("ERROR: {:?}", e),
+
+        summary.add_file(p, &file_summary);
+        continue;
+      }
     }
   }
-  Ok((matches, files_matched))
 }
 
-fn search_file(path: &str, conf: &Config, whitespaces_in_date: usize, oldest_ts: u64) -> Result<u64, (SearchError, u64)> {
-  let mmap;
-  let mut matches = 0;
-
-  let file_in = File::open(path).expect("cannot open file");
-  let metadata = file_in.metadata().expect("cannot get metadata");
-  if !metadata.is_file() {
-    return Err((SearchError::NotFile, 0));
-  } else if metadata.len() > isize::max_value() as u64 {
-    panic!("the file {} is too large to be safely mapped to memory:
-            https://github.com/danburkert/memmap-rs/issues/69", path);
-  } else if metadata.len() == 0 {
-    return Err((SearchError::EmptyFile, 0));
-  } 
-
-  let (file, len) = {
-    mmap = Mmap::open_path(path, memmap::Protection::Read).expect("cannot memmap");
-    let bytes = unsafe { mmap.as_slice() };
-    (bytes, mmap.len())
+/// Searches a single file. `live` files may be truncated by a rotation
+/// while they are searched. The counts are returned together with the error
+/// which stopped the search, if any.
+fn search_file(path: &str, conf: &Config, window: Window, live: bool) -> (FileSummary, Option<SearchError>) {
+  let failed = |err: SearchError| (FileSummary::default(), Some(err));
+  let io_error = |e: io::Error| failed(SearchError::Io(e.kind()));
+  let mut file_in = match File::open(path) {
+    Ok(file_in) => file_in,
+    Err(e) => return io_error(e),
+  };
+  let metadata = match file_in.metadata() {
+    Ok(metadata) => metadata,
+    Err(e) => return io_error(e),
+  };
+  let newline = conf.encoding.newline();
+
+  if !metadata.is_file() && !utils::is_fifo(&metadata) {
+    // directories and devices
+    return failed(SearchError::NotFile);
+  } else if conf.line_format == LineFormat::Journal {
+    let mut bytes = Vec::new();
+    if let Err(e) = file_in.read_to_end(&mut bytes) {
+      return io_error(e);
+    }
+    return search_journal(&bytes, conf, window);
+  } else if !metadata.is_file() || metadata.len() == 0 {
+    // pipes and files in /proc don't have a known length and can't be read
+    // backwards, they are read completely. the limit keeps a pipe which is
+    // never closed from blocking the check.
+    let mut bytes = Vec::new();
+    if let Err(e) = file_in.take(MAX_UNSIZED_LEN).read_to_end(&mut bytes) {
+      return io_error(e);
+    }
+    if bytes.len() as u64 == MAX_UNSIZED_LEN && conf.debug {
+      println!("only the first {} bytes of {} are searched", MAX_UNSIZED_LEN, path);
+    }
+    if bytes.is_empty() {
+      return failed(SearchError::EmptyFile);
+    }
+    return search_lines(&mut SliceLines::new(&bytes, newline), conf, window);
+  }
+
+  // a mapped file which is truncated while it is searched (e.g. by logrotate's
+  // copytruncate) kills the process with SIGBUS. live files, no matter how
+  // long they have been quiet, and recently written rotated files are
+  // therefore read instead. files larger than isize::MAX can't be mapped
+  // safely: https://github.com/danburkert/memmap-rs/issues/69
+  let len = metadata.len();
+  let is_active = metadata.modified().ok()
+    .and_then(|modified| modified.elapsed().ok())
+    .is_none_or(|elapsed| elapsed.as_secs() <= conf.active_file_age);
+
+  let mmap = if live || is_active || len > isize::MAX as u64 {
+    None
+  } else {
+    Mmap::open_path(path, memmap::Protection::Read).ok()
   };
 
-  let mut last_printed = len as i64;
-  let mut index = last_printed - 1;
-  while index >= -1 {
-    if index == -1 || file[index as usize] == '\n' as u8 {
-      let line = &file[(index + 1) as usize..last_printed as usize];
-      let is_match = search_line(line, whitespaces_in_date, oldest_ts, &conf);
-      match is_match {
-        Ok(v) => {
-          if v {
-            matches += 1;
-          }
-        },
-        Err(err) => {
-          return Err((err, matches));
-        }
+  let (mut counts, err) = match mmap {
+    Some(ref mmap) => {
+      let bytes = unsafe { mmap.as_slice() };
+      search_lines(&mut SliceLines::new(bytes, newline), conf, window)
+    },
+    None => {
+      if conf.debug {
+        println!("reading {} instead of mapping it to memory", path);
       }
+      search_lines(&mut ReverseReader::new(&mut file_in, len, newline), conf, window)
+    },
+  };
+
+  // the result is kept, e.g. a `TimestampTooOld` still means that the
+  // older files don't need to be searched
+  let len_after = file_in.metadata().map(|m| m.len()).unwrap_or(0);
+  if len_after < len {
+    if conf.debug {
+      println!("{} was truncated from {} to {} bytes during the search", path, len, len_after);
+    }
+    counts.truncated = true;
+  }
+
+  (counts, err)
+}
+
+/// Searches the entries of the journal from the newest to the oldest one.
+/// The messages of the entries are matched.
+fn search_journal(bytes: &[u8], conf: &Config, window: Window) -> (FileSummary, Option<SearchError>) {
+  let mut state = SearchState::default();
 
-      last_printed = index + 1;
+  for record in format::parse_journal(bytes).iter().rev() {
+    match search_entry(record, record.field("MESSAGE").unwrap_or(""), window, conf, &mut state) {
+      Ok(true) => state.counts.matches += 1,
+      Ok(false) => {},
+      Err(err) => return (state.counts, Some(err)),
     }
+  }
 
-    index -= 1;
+  (state.counts, None)
+}
+
+/// Searches the lines of a file from the end to the beginning.
+fn search_lines<S: LineSource>(source: &mut S, conf: &Config, window: Window) -> (FileSummary, Option<SearchError>) {
+  let mut state = SearchState::default();
+
+  loop {
+    let line = match source.prev_line() {
+      Ok(Some(line)) => line,
+      Ok(None) => break,
+      // the file got shorter while it was read
+      Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+        state.counts.truncated = true;
+        return (state.counts, Some(SearchError::Truncated));
+      },
+      Err(e) => return (state.counts, Some(SearchError::Io(e.kind()))),
+    };
+
+    let is_match = search_line(line, window, conf, &mut state);
+    match is_match {
+      Ok(v) => {
+        if v {
+          state.counts.matches += 1;
+        }
+      },
+      Err(err) => {
+        return (state.counts, Some(err));
+      }
+    }
+  }
+
+  // the first container log record of the file is complete now
+  if let Some(record) = state.pending.take() {
+    match search_entry(&record, record.field("log").unwrap_or(""), window, conf, &mut state) {
+      Ok(true) => state.counts.matches += 1,
+      Ok(false) => {},
+      Err(err) => return (state.counts, Some(err)),
+    }
   }
 
-  Ok(matches)
+  (state.counts, None)
 }
 
-fn search_line(bytes: &[u8], whitespaces_in_datefields: usize, oldest_ts: u64, conf: &Config) -> Result<bool, SearchError> {
+fn search_line(bytes: &[u8], window: Window, conf: &Config, state: &mut SearchState) -> Result<bool, SearchError> {
   if bytes.len() == 0 {
     return Ok(false);
   }
 
-  let l = str::from_utf8(bytes);
-  if l.is_err() {
+  let line = match conf.encoding.decode(bytes) {
+    None => {
       if conf.debug {
         println!("skipping file because not utf8 parseable!");
       }
       return Err(SearchError::NotUtf8);
-  }
-  let line = l.unwrap().trim();
-  if line.len() == 0 {
+    },
+    Some(Cow::Borrowed(line)) => Cow::Borrowed(trim_line(line, conf)),
+    Some(Cow::Owned(line)) => Cow::Owned(trim_line(&line, conf).to_owned()),
+  };
+  if line.is_empty() {
     return Ok(false);
   }
 
@@ -205,40 +473,199 @@ fn search_line(bytes: &[u8], whitespaces_in_datefields: usize, oldest_ts: u64, c
     println!("searching line: {}", line);
   }
 
-  let words: Vec<&str> = line.split_whitespace().collect();
-  let datefields = words.get(conf.timeposition..(conf.timeposition + whitespaces_in_datefields));
-  let extracted_date;
-  match datefields {
-    None => return Ok(false),
-    Some(fields) => {
-      extracted_date = fields.join(" ");
-    }
-  };
+  if conf.line_format != LineFormat::Plain {
+    return search_record(&line, window, conf, state);
+  }
 
-  let date = utils::parse_date(&extracted_date, &conf.date_pattern);
+  let date = extract_date(&line, conf, &mut state.last_format);
   match date {
-    None => Ok(false),
-    Some(date) => {
-      if conf.debug {
-        println!("parsed {} to date {}", extracted_date, date);
+    None => {
+      if conf.multiline {
+        state.continuation.push(line.into_owned());
       }
+      Ok(false)
+    },
+    Some(date) => {
+      // in multiline mode the line with the timestamp is the first line of
+      // an entry, the lines without timestamp after it belong to the entry.
+      let entry = if state.continuation.is_empty() {
+        line
+      } else {
+        let mut entry = line.into_owned();
+        for continuation_line in state.continuation.drain(..).rev() {
+          entry.push('\n');
+          entry.push_str(&continuation_line);
+        }
+        Cow::Owned(entry)
+      };
 
       let ts_line = utils::get_timestamp(date);
-      if oldest_ts > ts_line {
-        return Err(SearchError::TimestampTooOld);
+      let period = check_timestamp(ts_line, window, conf, &mut state.counts)?;
+      if period == Period::Skipped {
+        return Ok(false);
       }
 
-      let is_match = conf.re.captures_from_pos(&line, 0).unwrap();
-      let is_match = is_match.is_some();
-      if is_match && conf.verbose {
-        // no println, "\n" is already contained in line
-        print!("{}", line);
+      let captures = conf.re.captures_from_pos(&entry, 0).unwrap();
+      let is_match = captures.is_some();
+      if period == Period::Previous {
+        state.counts.previous_matches += is_match as u64;
+        return Ok(false);
+      }
+      if let Some(captures) = captures {
+        state.counts.add_match_time(ts_line);
+        add_distinct_value(&captures, conf, &mut state.counts);
+        if conf.verbose {
+          // no println, "\n" is already contained in line
+          print!("{}", entry);
+        }
       }
       Ok(is_match)
     }
   }
 }
 
+/// Trailing whitespace of partial cri lines belongs to the log line, so
+/// only the line ending is removed.
+fn trim_line<'a>(line: &'a str, conf: &Config) -> &'a str {
+  match conf.line_format {
+    LineFormat::Cri => line.trim_end_matches('\r'),
+    _ => line.trim(),
+  }
+}
+
+/// Searches a line of a structured format, e.g. json. Lines which can't be
+/// parsed or have no timestamp are skipped.
+fn search_record(line: &str, window: Window, conf: &Config, state: &mut SearchState) -> Result<bool, SearchError> {
+  let record = match Record::parse(conf.line_format, line) {
+    None => return Ok(false),
+    Some(record) => record,
+  };
+  if !conf.line_format.is_container() {
+    return search_entry(&record, line, window, conf, state);
+  }
+
+  // the lines are read backwards, so the partial records of a container
+  // log line are read after its last record. the line is searched once
+  // the record before its first part is read.
+  if record.is_partial() {
+    match state.pending {
+      Some(ref mut pending) => pending.prepend(&record),
+      // the line is still being written
+      None => state.pending = Some(record),
+    }
+    return Ok(false);
+  }
+  match state.pending.replace(record) {
+    None => Ok(false),
+    Some(complete) => search_entry(&complete, complete.field("log").unwrap_or(""), window, conf, state),
+  }
+}
+
+/// Searches a record, the pattern is matched against `text` unless it is
+/// restricted to fields.
+fn search_entry(record: &Record, text: &str, window: Window, conf: &Config, state: &mut SearchState) -> Result<bool, SearchError> {
+  let ts_line = match record.timestamp(conf) {
+    None => return Ok(false),
+    Some(ts_line) => ts_line,
+  };
+  let period = check_timestamp(ts_line, window, conf, &mut state.counts)?;
+  if period == Period::Skipped {
+    return Ok(false);
+  }
+
+  let matched = record.find_match(conf, text);
+  if period == Period::Previous {
+    state.counts.previous_matches += matched.is_some() as u64;
+    return Ok(false);
+  }
+
+  if conf.line_format == LineFormat::Access && record.passes_filters(conf) {
+    state.counts.requests.add_request(record.status(), record.response_time_ms());
+  }
+  let is_match = matched.is_some();
+  if let Some(matched) = matched {
+    state.counts.add_match_time(ts_line);
+    if conf.distinct_group.is_some() {
+      if let Ok(Some(captures)) = conf.re.captures(matched) {
+        add_distinct_value(&captures, conf, &mut state.counts);
+      }
+    }
+    if conf.verbose {
+      println!("{}", text);
+    }
+  }
+  Ok(is_match)
+}
+
+/// Adds the value of the capture group which is counted distinctly.
+fn add_distinct_value(captures: &fancy_regex::Captures, conf: &Config, counts: &mut FileSummary) {
+  if let Some(value) = conf.distinct_group.and_then(|group| captures.at(group)) {
+    counts.distinct.insert(value, conf.distinct_approximate);
+  }
+}
+
+/// Counts a line with the timestamp `ts_line`. Returns the interval of the
+/// line and an error if the search in the file can be stopped.
+fn check_timestamp(ts_line: u64, window: Window, conf: &Config, counts: &mut FileSummary) -> Result<Period, SearchError> {
+  // the lines are read backwards, so apart from out-of-order lines the
+  // first timestamp is the newest one
+  if counts.newest_ts.is_none_or(|newest_ts| ts_line > newest_ts) {
+    counts.newest_ts = Some(ts_line);
+  }
+
+  if window.oldest_ts > ts_line {
+    // lines of multi-threaded applications can be slightly out of order,
+    // lines in the tolerance are skipped but don't stop the search.
+    if window.oldest_ts.saturating_sub(conf.skew_tolerance) > ts_line {
+      return Err(SearchError::TimestampTooOld);
+    }
+    return Ok(Period::Skipped);
+  }
+  if window.current_ts > ts_line {
+    return Ok(Period::Previous);
+  }
+
+  counts.dated_lines += 1;
+  if ts_line > window.newest_ts {
+    if conf.debug {
+      println!("timestamp of line is in the future: {}", NaiveDateTime::from_timestamp(ts_line as i64, 0));
+    }
+    counts.future_lines += 1;
+    if conf.exclude_future {
+      return Ok(Period::Skipped);
+    }
+  }
+
+  Ok(Period::Current)
+}
+
+/// Tries the configured date formats on the line, starting with the one
+/// which was successful for the previous line.
+fn extract_date(line: &str, conf: &Config, last_format: &mut usize) -> Option<DateTime<Utc>> {
+  let words: Vec<&str> = line.split_whitespace().collect();
+  let last = *last_format;
+  let others = (0..conf.date_formats.len()).filter(|&i| i != last);
+
+  for i in std::iter::once(last).chain(others) {
+    let format = &conf.date_formats[i];
+    let extracted_date = match words.get(format.position..(format.position + format.fields)) {
+      None => continue,
+      Some(fields) => fields.join(" "),
+    };
+
+    let extracted_date = conf.locale.to_english(&extracted_date);
+    if let Some(date) = utils::parse_date(&extracted_date, &format.pattern) {
+      if conf.debug {
+        println!("parsed {} to date {}", extracted_date, date);
+      }
+      *last_format = i;
+      return Some(date);
+    }
+  }
+
+  None
+}
+
 #[cfg(test)]
 mod tests {
   extern crate filetime;
@@ -435,15 +862,50 @@ mod tests {
     // given
     let path = "./fixtures/1x1.png";
     let conf = get_dummy_conf(forever(), DUMMY_SEARCH_PATTERN.to_owned(), path.to_owned());
-    let whitespaces_in_date = 3; // doesn't matter, should not be considered anyway
-    let oldest_ts = forever();
+    let window = Window { oldest_ts: forever(), current_ts: forever(), newest_ts: u64::MAX, now_ts: 0 };
 
     // when
-    let res = search_file(path, &conf, whitespaces_in_date, oldest_ts);
+    let res = search_file(path, &conf, window, false);
 
     // then
-    let files_matched = 0;
-    assert_eq!(res, Err((SearchError::NotUtf8, files_matched)));
+    assert_eq!(res, (FileSummary::default(), Some(SearchError::NotUtf8)));
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn should_search_fifos() {
+    // given
+    let dir = self::tempfile::TempDir::new().unwrap();
+    let fifo = dir.path().join("app.fifo");
+    let status = std::process::Command::new("mkfifo").arg(&fifo).status().unwrap();
+    assert!(status.success());
+    let writer_path = fifo.clone();
+    let writer = std::thread::spawn(move || {
+      std::fs::write(writer_path, "2018-09-13 00:03:01 foo\n2018-09-13 00:03:02 foo\n").unwrap();
+    });
+    let conf = get_dummy_conf(forever(), "foo".to_owned(), "".to_owned());
+    let window = Window { oldest_ts: 0, current_ts: 0, newest_ts: u64::MAX, now_ts: 0 };
+
+    // when
+    let res = search_file(fifo.to_str().unwrap(), &conf, window, false);
+
+    // then
+    writer.join().unwrap();
+    assert_eq!((res.0.matches, res.1), (2, None));
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn should_skip_devices() {
+    // given
+    let conf = get_dummy_conf(forever(), "foo".to_owned(), "".to_owned());
+    let window = Window { oldest_ts: 0, current_ts: 0, newest_ts: u64::MAX, now_ts: 0 };
+
+    // when
+    let res = search_file("/dev/zero", &conf, window, false);
+
+    // then
+    assert_eq!(res, (FileSummary::default(), Some(SearchError::NotFile)));
   }
 
   #[test]
@@ -461,6 +923,38 @@ mod tests {
     assert_eq!(res, Ok((matches, files_matched)));
   }
 
+  #[test]
+  fn should_search_files_in_other_encodings() {
+    // given
+    let mut latin1 = NamedTempFile::new().unwrap();
+    latin1.write_all(b"2018-09-13 00:03:01 caf\xe9\n2018-09-13 00:03:02 caf\xe9\n").unwrap();
+    let mut utf16 = NamedTempFile::new().unwrap();
+    let utf16_content: Vec<u8> = "\u{feff}2018-09-13 00:03:01 café\r\n2018-09-13 00:03:02 café\r\n"
+      .encode_utf16()
+      .flat_map(|unit| unit.to_le_bytes().to_vec())
+      .collect();
+    utf16.write_all(&utf16_content).unwrap();
+    let window = Window { oldest_ts: 0, current_ts: 0, newest_ts: u64::MAX, now_ts: 0 };
+
+    let mut conf = get_dummy_conf(forever(), "café".to_owned(), "".to_owned());
+    let search = |conf: &Config, file: &NamedTempFile| {
+      let (counts, err) = search_file(file.path().to_str().unwrap(), conf, window, false);
+      (counts.matches, err)
+    };
+
+    // when
+    let strict = search(&conf, &latin1);
+    conf.encoding = Encoding::Latin1;
+    let decoded = search(&conf, &latin1);
+    conf.encoding = Encoding::Utf16Le;
+    let decoded_utf16 = search(&conf, &utf16);
+
+    // then
+    assert_eq!(strict.1, Some(SearchError::NotUtf8));
+    assert_eq!(decoded, (2, None));
+    assert_eq!(decoded_utf16, (2, None));
+  }
+
   #[test]
   fn should_handle_files_with_lines_without_dates() {
     // given
@@ -556,6 +1050,96 @@ mod tests {
     assert_eq!(res, Ok((matches, files_matched)));
   }
 
+  #[test]
+  fn should_fall_back_to_further_date_formats() {
+    // given
+    let format = "%Y-%m-%d %H:%M:%S";
+    let (_file, path) = create_temp_file("2018-09-13 00:03:01 foo\nlib [13/Sep/2018:00:03:02] foo\n2018-09-13 00:03:03 foo\nno date foo\n");
+    let mut conf = get_dummy_conf_format(forever(), "foo".to_owned(), path, format.to_owned(), 0);
+    conf.add_date_format("[%d/%b/%Y:%H:%M:%S]".to_owned(), 1);
+
+    // when
+    let res = run(&conf);
+
+    // then
+    let matches = 3;
+    let files_matched = 1;
+    assert_eq!(res, Ok((matches, files_matched)));
+  }
+
+  #[test]
+  fn should_continue_past_out_of_order_lines_within_skew_tolerance() {
+    // given
+    reset_tz();
+    let now_unix_ts = get_now_secs();
+    let format = "%b %d %H:%M:%S";
+
+    let dt = NaiveDateTime::from_timestamp(now_unix_ts as i64, 0);
+    let now_formatted = dt.format(format).to_string();
+
+    let five_minutes = now_unix_ts - (5 * 60);
+    let dt_five_minutes_ago = NaiveDateTime::from_timestamp(five_minutes as i64, 0);
+    let five_minutes_ago = dt_five_minutes_ago.format(format).to_string();
+
+    let content = format!("{} foo\n{} foo\n{} foo",
+                           now_formatted, five_minutes_ago, now_formatted);
+    let (_file, path) = create_temp_file(&content);
+
+    let interval_to_check: u64 = 2;
+    let mut conf = get_dummy_conf_format(interval_to_check, "foo".to_owned(), path, format.to_owned(), 0);
+    conf.skew_tolerance = 10 * 60;
+
+    // when
+    let res = run(&conf);
+
+    // then
+    // the delayed line is not counted, but the line before it is
+    let matches = 2;
+    let files_matched = 1;
+    assert_eq!(res, Ok((matches, files_matched)));
+  }
+
+  #[test]
+  fn should_match_continuation_lines_in_multiline_mode() {
+    // given
+    let content = "2018-09-13 00:03:01 ERROR request failed
+java.lang.NullPointerException: foo
+\tat com.example.Foo.bar(Foo.java:42)
+2018-09-13 00:03:02 INFO request ok
+2018-09-13 00:03:03 ERROR request failed
+java.lang.IllegalStateException: bar";
+    let (_file, path) = create_temp_file(content);
+    let mut conf = get_dummy_conf(forever(), "failed\\s+java.lang.NullPointerException".to_owned(), path);
+    conf.multiline = true;
+
+    // when
+    let res = run(&conf);
+
+    // then
+    let matches = 1;
+    let files_matched = 1;
+    assert_eq!(res, Ok((matches, files_matched)));
+  }
+
+  #[test]
+  fn should_count_lines_from_the_future() {
+    // given
+    let (_file, path) = create_temp_file("2018-09-13 00:03:01 foo\n2099-09-13 00:03:01 foo\n2099-09-13 00:03:02 foo");
+    let mut conf = get_dummy_conf(forever(), "foo".to_owned(), path.clone());
+
+    // when
+    let res = run_summary(&conf).unwrap();
+    conf.exclude_future = true;
+    let res_excluded = run_summary(&conf).unwrap();
+
+    // then
+    assert_eq!(res.matches, 3);
+    assert_eq!(res.future_lines, 2);
+    assert_eq!(res.future_files, vec![path]);
+    assert_eq!(res_excluded.matches, 1);
+    assert_eq!(res.alerts(&conf).len(), 1);
+  }
+
   #[test]
   fn should_skip_old_files() {
     // given
@@ -610,6 +1194,468 @@ mod tests {
     assert_eq!(res, Ok((matches, files_matched)));
   }
 
+  #[test]
+  fn should_add_up_matches_of_multiple_logfiles() {
+    // given
+    let (_file1, path1) = create_temp_file("2018-09-13 00:03:01 foo\n2018-09-13 00:03:02 foo");
+    let (_file2, path2) = create_temp_file("2018-09-13 00:03:01 foo");
+    let (_list, list_path) = create_temp_file(&format!("# comment\n{}\n\n./fixtures/logfile.0", path2));
+    let mut conf = get_dummy_conf(forever(), "foo".to_owned(), path1);
+    for logfile in read_logfile_list(&list_path).unwrap() {
+      conf.add_logfile(logfile).unwrap();
+    }
+
+    // when
+    let res = run(&conf);
+
+    // then
+    let matches = 4;
+    let files_matched = 3;
+    assert_eq!(res, Ok((matches, files_matched)));
+  }
+
+  #[test]
+  fn should_report_why_a_logfile_list_can_not_be_read() {
+    // given
+    let path = "/tmp/some-missing-list";
+
+    // when
+    let res = read_logfile_list(path);
+
+    // then
+    let err = res.unwrap_err();
+    assert_eq!(err, ConfigError::LogfileListUnreadable(path.to_owned(), io::ErrorKind::NotFound));
+    assert_eq!(String::from(err), "-logfilelist /tmp/some-missing-list can not be read: entity not found");
+  }
+
+  #[test]
+  fn should_break_down_matches_by_directory() {
+    // given
+    let dir = self::tempfile::TempDir::new().unwrap();
+    for &(tenant, content) in &[("a", "2018-09-13 00:03:01 foo\n2018-09-13 00:03:02 foo\n"), ("b", "2018-09-13 00:03:01 foo\n")] {
+      std::fs::create_dir(dir.path().join(tenant)).unwrap();
+      std::fs::write(dir.path().join(tenant).join("app.log"), content).unwrap();
+    }
+    let mut conf = get_dummy_conf(forever(), "foo".to_owned(), "".to_owned());
+    let root = dir.path().to_str().unwrap().to_owned();
+    conf.add_logdir(root.clone());
+
+    // when
+    let res = run_summary(&conf).unwrap();
+
+    // then
+    assert_eq!(res.matches, 3);
+    assert_eq!(res.files_matched, 2);
+    let by_directory: Vec<(String, u64)> = res.matches_by_directory().into_iter().collect();
+    assert_eq!(by_directory, vec![(format!("{}/a", root), 2), (format!("{}/b", root), 1)]);
+  }
+
+  #[test]
+  fn should_search_unrelated_log_files_of_a_glob_separately() {
+    // given
+    let dir = self::tempfile::TempDir::new().unwrap();
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let a = dir.path().join("a.log");
+    let b = dir.path().join("b.log");
+    std::fs::write(&a, format!("2018-09-13 00:03:01 bar\n{} bar\n", now)).unwrap();
+    std::fs::write(&b, format!("{} foo\n", now)).unwrap();
+    // b.log was modified before a.log
+    let earlier = FileTime::from_unix_time(get_now_secs() as i64 - 30, 0);
+    filetime::set_file_times(&b, earlier, earlier).unwrap();
+    let glob = dir.path().join("*.log").to_str().unwrap().to_owned();
+    let conf = get_dummy_conf(5, "foo".to_owned(), glob);
+
+    // when
+    let res = run(&conf);
+
+    // then
+    let matches = 1;
+    let files_matched = 2;
+    assert_eq!(res, Ok((matches, files_matched)));
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn should_report_paths_which_are_not_utf8() {
+    // given
+    use std::os::unix::ffi::OsStrExt;
+    let dir = self::tempfile::TempDir::new().unwrap();
+    std::fs::write(dir.path().join("app.log"), "2018-09-13 00:03:01 foo\n").unwrap();
+    let name = std::ffi::OsStr::from_bytes(b"bad\xff.log");
+    std::fs::write(dir.path().join(name), "2018-09-13 00:03:01 foo\n").unwrap();
+    let mut conf = get_dummy_conf(forever(), "foo".to_owned(), "".to_owned());
+    conf.add_logdir(dir.path().to_str().unwrap().to_owned());
+
+    // when
+    let res = run_summary(&conf).unwrap();
+
+    // then
+    assert_eq!(res.matches, 1);
+    assert_eq!(res.files_matched, 2);
+    assert_eq!(res.failed_files.len(), 1);
+    assert_eq!(res.failed_files[0].1, "path is not valid utf-8");
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn should_search_linked_files_only_once() {
+    // given
+    let dir = self::tempfile::TempDir::new().unwrap();
+    let logfile = dir.path().join("app.log");
+    std::fs::write(&logfile, "2018-09-13 00:03:01 foo\n").unwrap();
+    // links which look like rotated versions of the log file
+    std::os::unix::fs::symlink(&logfile, dir.path().join("app.log.1")).unwrap();
+    std::fs::hard_link(&logfile, dir.path().join("app.log.2")).unwrap();
+    // a further log file which is the same file
+    let current = dir.path().join("current.log");
+    std::os::unix::fs::symlink(&logfile, &current).unwrap();
+    let mut conf = get_dummy_conf(forever(), "foo".to_owned(), logfile.to_str().unwrap().to_owned());
+
+    // when
+    let res = run(&conf);
+    conf.add_logfile(current.to_str().unwrap().to_owned()).unwrap();
+    let res_same_logfile = run(&conf);
+
+    // then
+    let matches = 1;
+    let files_matched = 1;
+    assert_eq!(res, Ok((matches, files_matched)));
+    assert_eq!(res_same_logfile, Ok((matches, files_matched)));
+  }
+
+  #[test]
+  fn should_detect_stale_logs() {
+    // given
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
+    let (_stale_file, stale_path) = create_temp_file("2018-09-13 00:03:01 foo");
+    let (_fresh_file, fresh_path) = create_temp_file(&format!("{} foo", now));
+    let mut stale_conf = get_dummy_conf(forever(), "foo".to_owned(), stale_path);
+    stale_conf.stale_warning = Some(5);
+    stale_conf.stale_critical = Some(60);
+    let mut fresh_conf = get_dummy_conf(forever(), "foo".to_owned(), fresh_path);
+    fresh_conf.stale_critical = Some(5);
+
+    // when
+    let stale = run_summary(&stale_conf).unwrap();
+    let fresh = run_summary(&fresh_conf).unwrap();
+
+    // then
+    assert_eq!(stale.newest_file_age, Some(0));
+    assert_eq!(stale.alerts(&stale_conf)[0].0, State::Critical);
+    assert!(fresh.newest_line_age.unwrap() < 5 * 60);
+    assert!(fresh.alerts(&fresh_conf).is_empty());
+  }
+
+  #[test]
+  fn should_match_fields_of_json_lines() {
+    // given
+    let content = [
+      r#"{"ts":"2018-09-13T00:00:01Z","level":"info","msg":"timeout"}"#,
+      r#"not json"#,
+      r#"{"ts":1536796982,"level":"error","msg":"connection timeout"}"#,
+      r#"{"ts":"2018-09-13 00:03:03","level":"error","msg":"disk full","user":"timeout"}"#,
+    ].join("\n");
+    let (_file, path) = create_temp_file(&content);
+    let mut conf = get_dummy_conf(forever(), "timeout".to_owned(), path);
+    conf.line_format = LineFormat::Json;
+    conf.add_filter("level=error").unwrap();
+    conf.match_fields.push("msg".to_owned());
+
+    // when
+    let res = run(&conf);
+
+    // then
+    assert_eq!(res, Ok((1, 1)));
+  }
+
+  #[test]
+  fn should_search_the_journal() {
+    // given
+    let content = [
+      r#"{"__REALTIME_TIMESTAMP":"1536796981000000","MESSAGE":"connect timeout","_SYSTEMD_UNIT":"nginx.service","PRIORITY":"6"}"#,
+      r#"{"__REALTIME_TIMESTAMP":"1536796982000000","MESSAGE":"upstream timeout","_SYSTEMD_UNIT":"nginx.service","PRIORITY":"3"}"#,
+      r#"{"__REALTIME_TIMESTAMP":"1536796983000000","MESSAGE":"timeout","_SYSTEMD_UNIT":"sshd.service","PRIORITY":"3"}"#,
+    ].join("\n");
+    let (_file, path) = create_temp_file(&content);
+    let mut conf = get_dummy_conf(forever(), "timeout".to_owned(), path);
+    conf.line_format = LineFormat::Journal;
+    conf.add_filter("_SYSTEMD_UNIT=nginx.service").unwrap();
+    conf.set_max_severity("err").unwrap();
+
+    // when
+    let res = run(&conf);
+
+    // then
+    assert_eq!(res, Ok((1, 1)));
+  }
+
+  #[test]
+  fn should_compare_matches_with_the_lines_in_the_interval() {
+    // given
+    let content = "2018-09-13 00:03:01 foo\n2018-09-13 00:03:02 bar\nno date foo\n2018-09-13 00:03:03 bar\n2018-09-13 00:03:04 bar";
+    let (_file, path) = create_temp_file(content);
+    let mut conf = get_dummy_conf(forever(), "foo".to_owned(), path);
+    conf.max_warning_matches = u64::MAX;
+    conf.warning_percentage = Some(20.0);
+    conf.critical_percentage = Some(25.0);
+
+    // when
+    let res = run_summary(&conf).unwrap();
+
+    // then
+    assert_eq!((res.matches, res.dated_lines), (1, 4));
+    assert_eq!(res.match_percentage(), Some(25.0));
+    assert!(res.reaches(conf.max_warning_matches, conf.warning_percentage));
+    assert!(res.reaches(u64::MAX, conf.critical_percentage));
+    assert!(!res.reaches(u64::MAX, Some(30.0)));
+    assert_eq!(res.perfdata(&conf), "matches=1;;1;0 lines=4;;;0 ratio=25.00%;20;25;0;100");
+    conf.warning_percentage = None;
+    conf.critical_percentage = None;
+    assert_eq!(res.perfdata(&conf), "");
+  }
+
+  #[test]
+  fn should_find_bursts_of_matches() {
+    // given
+    let content = [
+      "2018-09-13 00:01:59 foo",
+      "2018-09-13 00:03:01 foo",
+      "2018-09-13 00:03:30 foo",
+      "2018-09-13 00:04:10 foo",
+      "2018-09-13 00:04:20 bar",
+      "2018-09-13 00:05:01 foo",
+    ].join("\n");
+    let (_file, path) = create_temp_file(&content);
+    let mut conf = get_dummy_conf(forever(), "foo".to_owned(), path);
+    conf.burst_minutes = 2;
+    conf.burst_critical = Some(3);
+
+    // when
+    let res = run_summary(&conf).unwrap();
+
+    // then
+    assert_eq!(res.max_matches_within(1), 2);
+    assert_eq!(res.max_matches_within(2), 3);
+    assert_eq!(res.max_matches_within(u64::MAX), 5);
+    let histogram: Vec<u64> = res.histogram().into_iter().map(|(_, matches)| matches).collect();
+    assert_eq!(histogram, vec![1, 0, 2, 1, 1]);
+    assert_eq!(res.histogram()[0].0, "2018-09-13 00:01");
+    assert!(res.alerts(&conf).is_empty());
+    conf.burst_critical = Some(2);
+    assert_eq!(res.alerts(&conf)[0].0, State::Critical);
+    conf.burst_minutes = 0;
+    assert_eq!(conf.validate(), Err(ConfigError::BurstMinutesInvalid));
+  }
+
+  #[test]
+  fn should_compare_matches_with_the_previous_interval() {
+    // given
+    let minutes_ago = |minutes: i64| (chrono::Local::now() - chrono::Duration::minutes(minutes)).format("%Y-%m-%d %H:%M:%S").to_string();
+    let content = [
+      format!("{} foo", minutes_ago(25)),
+      format!("{} foo", minutes_ago(8)),
+      format!("{} foo", minutes_ago(7)),
+      format!("{} foo", minutes_ago(3)),
+      format!("{} foo", minutes_ago(2)),
+      format!("{} foo", minutes_ago(1)),
+    ].join("\n");
+    let (_file, path) = create_temp_file(&content);
+    let mut conf = get_dummy_conf(5, "foo".to_owned(), path);
+    conf.trend_warning = Some("40%".parse().unwrap());
+    conf.trend_critical = Some("2".parse().unwrap());
+
+    // when
+    let res = run_summary(&conf).unwrap();
+
+    // then
+    assert_eq!((res.matches, res.previous_matches), (3, 2));
+    assert_eq!(res.alerts(&conf)[0].0, State::Warning);
+    assert!(!Growth::Matches(u64::MAX).is_exceeded(2, 3));
+  }
+
+  #[test]
+  fn should_count_distinct_values_of_a_group() {
+    // given
+    let content = [
+      "2018-09-13 00:03:01 sshd: Failed password for root from 10.0.0.1 port 22",
+      "2018-09-13 00:03:02 sshd: Failed password for admin from 10.0.0.2 port 22",
+      "2018-09-13 00:03:03 sshd: Accepted password for root from 10.0.0.3 port 22",
+      "2018-09-13 00:03:04 sshd: Failed password for root from 10.0.0.1 port 22",
+    ].join("\n");
+    let (_file, path) = create_temp_file(&content);
+    let pattern = r"Failed (?:password) for (\S+) from (?P<ip>[^\s(]+)";
+    let mut conf = get_dummy_conf(forever(), pattern.to_owned(), path);
+    conf.set_distinct_group("ip").unwrap();
+    conf.distinct_critical = Some(2);
+
+    // when
+    let res = run_summary(&conf).unwrap();
+
+    // then
+    assert_eq!(conf.distinct_group, Some(2));
+    assert_eq!(res.matches, 3);
+    assert_eq!(res.distinct.count(), 2);
+    assert_eq!(res.alerts(&conf)[0].0, State::Critical);
+    assert!(conf.set_distinct_group("user").is_err());
+  }
+
+  #[test]
+  fn should_aggregate_requests_of_access_logs() {
+    // given
+    let content = [
+      r#"::1 - - [13/Sep/2018:00:03:01 +0000] "GET /api HTTP/1.1" 200 12 "-" "-" 0.100"#,
+      r#"::1 - - [13/Sep/2018:00:03:02 +0000] "GET /api HTTP/1.1" 502 12 "-" "-" 0.900"#,
+      r#"::1 - - [13/Sep/2018:00:03:03 +0000] "GET /api HTTP/1.1" 404 12 "-" "-" 0.200"#,
+      r#"::1 - - [13/Sep/2018:00:03:04 +0000] "GET /static HTTP/1.1" 500 12 "-" "-" 0.000"#,
+    ].join("\n");
+    let (_file, path) = create_temp_file(&content);
+    let mut conf = get_dummy_conf(forever(), "^/api".to_owned(), path);
+    conf.line_format = LineFormat::Access;
+    conf.match_fields.push("path".to_owned());
+    conf.add_filter("path~^/api").unwrap();
+    conf.error_rate_critical = Some(30.0);
+    conf.latency_warning = Some(300);
+
+    // when
+    let res = run_summary(&conf).unwrap();
+
+    // then
+    assert_eq!(res.matches, 3);
+    assert_eq!(res.requests.count, 3);
+    assert_eq!(res.requests.status_classes, [0, 1, 0, 1, 1]);
+    assert_eq!(res.requests.response_time_ms_max, 900);
+    assert_eq!(res.alerts(&conf).iter().map(|alert| alert.0).collect::<Vec<_>>(), vec![State::Critical, State::Warning]);
+  }
+
+  #[test]
+  fn should_reassemble_partial_cri_lines() {
+    // given
+    let content = [
+      "2018-09-13T00:03:01.000000001Z stdout F db ok",
+      "2018-09-13T00:03:02.000000001Z stderr P db connection ",
+      "2018-09-13T00:03:02.000000002Z stderr P to primary: ",
+      "2018-09-13T00:03:02.000000003Z stderr F timeout",
+      "2018-09-13T00:03:03.000000001Z stdout P still writing db ",
+    ].join("\n");
+    let (_file, path) = create_temp_file(&content);
+    let mut conf = get_dummy_conf(forever(), "^db connection to primary: timeout$".to_owned(), path.clone());
+    conf.line_format = LineFormat::Cri;
+    let mut partial_conf = get_dummy_conf(forever(), "^still writing db $".to_owned(), path);
+    partial_conf.line_format = LineFormat::Cri;
+
+    // when
+    let res = run(&conf);
+    let res_partial = run(&partial_conf);
+
+    // then
+    assert_eq!(res, Ok((1, 1)));
+    assert_eq!(res_partial, Ok((1, 1)));
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn should_report_files_which_can_not_be_read() {
+    // given
+    let dir = self::tempfile::TempDir::new().unwrap();
+    let logfile = dir.path().join("app.log");
+    std::fs::write(&logfile, "2018-09-13 00:03:01 foo\n").unwrap();
+    std::os::unix::fs::symlink(dir.path().join("missing"), dir.path().join("app.log.1")).unwrap();
+    let conf = get_dummy_conf(forever(), "foo".to_owned(), logfile.to_str().unwrap().to_owned());
+
+    // when
+    let res = run_summary(&conf).unwrap();
+
+    // then
+    let failed = dir.path().join("app.log.1").to_str().unwrap().to_owned();
+    assert_eq!(res.matches, 1);
+    assert_eq!(res.failed_files, vec![(failed, "entity not found".to_owned())]);
+    assert_eq!(res.alerts(&conf)[0].0, State::Unknown);
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn should_not_check_files_after_the_cutoff() {
+    // given
+    let dir = self::tempfile::TempDir::new().unwrap();
+    let logfile = dir.path().join("app.log");
+    std::fs::write(&logfile, "2018-09-13 00:03:01 foo\n").unwrap();
+    std::os::unix::fs::symlink(dir.path().join("missing"), dir.path().join("app.log.1")).unwrap();
+    let conf = get_dummy_conf(CHECK_LAST_MINUTE, "foo".to_owned(), logfile.to_str().unwrap().to_owned());
+
+    // when
+    let res = run_summary(&conf).unwrap();
+
+    // then
+    assert_eq!(res.matches, 0);
+    assert_eq!(res.files_matched, 2);
+    assert!(res.failed_files.is_empty());
+    assert!(res.alerts(&conf).is_empty());
+  }
+
+  /// Returns the lines and then fails like a file which got shorter while
+  /// it was read.
+  struct TruncatedSource(Vec<&'static [u8]>);
+
+  impl LineSource for TruncatedSource {
+    fn prev_line(&mut self) -> io::Result<Option<&[u8]>> {
+      self.0.pop().map(Some).ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "truncated"))
+    }
+  }
+
+  #[test]
+  fn should_keep_the_matches_of_truncated_files() {
+    // given
+    let conf = get_dummy_conf(forever(), "foo".to_owned(), "".to_owned());
+    let window = Window { oldest_ts: 0, current_ts: 0, newest_ts: u64::MAX, now_ts: 0 };
+    let mut source = TruncatedSource(vec![&b"2018-09-13 00:03:01 bar"[..], &b"2018-09-13 00:03:02 foo"[..]]);
+
+    // when
+    let res = search_lines(&mut source, &conf, window);
+
+    // then
+    let (counts, err) = res;
+    assert_eq!(err, Some(SearchError::Truncated));
+    assert_eq!(counts.matches, 1);
+    assert!(counts.truncated);
+  }
+
+  #[test]
+  fn should_raise_the_truncated_state() {
+    // given
+    let mut conf = get_dummy_conf(forever(), "foo".to_owned(), SOME_LOG_FILE.to_owned());
+    let mut summary = Summary::default();
+    let truncated = FileSummary { matches: 1, truncated: true, ..FileSummary::default() };
+
+    // when
+    summary.add_file(SOME_LOG_FILE, &truncated);
+    let warning = summary.alerts(&conf);
+    conf.truncated_state = State::Critical;
+    let critical = summary.alerts(&conf);
+    conf.truncated_state = State::Ok;
+    let ok = summary.alerts(&conf);
+
+    // then
+    assert_eq!(summary.truncated_files, vec![SOME_LOG_FILE.to_owned()]);
+    assert_eq!(warning.len(), 1);
+    assert_eq!(warning[0].0, State::Warning);
+    assert!(warning[0].1.contains(SOME_LOG_FILE));
+    assert_eq!(critical[0].0, State::Critical);
+    assert!(ok.is_empty());
+  }
+
+  #[test]
+  fn should_abort_without_logfile() {
+    // given
+    let conf = get_dummy_conf(forever(), "foobar".to_owned(), "".to_owned());
+
+    // when
+    let res = run(&conf);
+
+    // then
+    assert_eq!(conf.validate(), Err(ConfigError::LogfileRequired));
+    assert_eq!(res, Err(ConfigError::LogfileRequired.into()));
+  }
+
   #[test]
   fn should_abort_when_stdin_used_as_logfile() {
     // given
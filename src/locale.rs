@@ -0,0 +1,181 @@
+extern crate std;
+
+use std::borrow::Cow;
+use std::str::FromStr;
+use ConfigError;
+
+/// Language of the month and weekday names in the log files. chrono
+/// only parses english names, so localized names are translated to
+/// english before a date is parsed.
+#[derive(Debug, Clone, PartialEq, Eq, Copy)]
+pub enum Locale {
+  En,
+  De,
+  Fr,
+  Es,
+  It,
+  Nl,
+}
+
+// month names come before the weekday names, in spanish and italian
+// `mar` is both an abbreviation for march and for tuesday. since dates
+// in logs contain months far more often than weekdays the month wins.
+const DE: &[(&str, &str)] = &[
+  ("januar", "Jan"), ("jan", "Jan"), ("jän", "Jan"), ("jänner", "Jan"),
+  ("februar", "Feb"), ("feb", "Feb"), ("märz", "Mar"), ("mär", "Mar"), ("mrz", "Mar"),
+  ("april", "Apr"), ("apr", "Apr"), ("mai", "May"), ("juni", "Jun"), ("jun", "Jun"),
+  ("juli", "Jul"), ("jul", "Jul"), ("august", "Aug"), ("aug", "Aug"),
+  ("september", "Sep"), ("sept", "Sep"), ("sep", "Sep"), ("oktober", "Oct"), ("okt", "Oct"),
+  ("november", "Nov"), ("nov", "Nov"), ("dezember", "Dec"), ("dez", "Dec"),
+  ("montag", "Mon"), ("mo", "Mon"), ("dienstag", "Tue"), ("di", "Tue"),
+  ("mittwoch", "Wed"), ("mi", "Wed"), ("donnerstag", "Thu"), ("do", "Thu"),
+  ("freitag", "Fri"), ("fr", "Fri"), ("samstag", "Sat"), ("sa", "Sat"),
+  ("sonntag", "Sun"), ("so", "Sun"),
+];
+
+const FR: &[(&str, &str)] = &[
+  ("janvier", "Jan"), ("janv", "Jan"), ("février", "Feb"), ("févr", "Feb"), ("fév", "Feb"),
+  ("mars", "Mar"), ("avril", "Apr"), ("avr", "Apr"), ("mai", "May"), ("juin", "Jun"),
+  ("juillet", "Jul"), ("juil", "Jul"), ("août", "Aug"), ("aout", "Aug"),
+  ("septembre", "Sep"), ("sept", "Sep"), ("octobre", "Oct"), ("oct", "Oct"),
+  ("novembre", "Nov"), ("nov", "Nov"), ("décembre", "Dec"), ("déc", "Dec"),
+  ("lundi", "Mon"), ("lun", "Mon"), ("mardi", "Tue"), ("mar", "Tue"),
+  ("mercredi", "Wed"), ("mer", "Wed"), ("jeudi", "Thu"), ("jeu", "Thu"),
+  ("vendredi", "Fri"), ("ven", "Fri"), ("samedi", "Sat"), ("sam", "Sat"),
+  ("dimanche", "Sun"), ("dim", "Sun"),
+];
+
+const ES: &[(&str, &str)] = &[
+  ("enero", "Jan"), ("ene", "Jan"), ("febrero", "Feb"), ("feb", "Feb"),
+  ("marzo", "Mar"), ("mar", "Mar"), ("abril", "Apr"), ("abr", "Apr"), ("mayo", "May"), ("may", "May"),
+  ("junio", "Jun"), ("jun", "Jun"), ("julio", "Jul"), ("jul", "Jul"), ("agosto", "Aug"), ("ago", "Aug"),
+  ("septiembre", "Sep"), ("setiembre", "Sep"), ("sept", "Sep"), ("sep", "Sep"), ("set", "Sep"),
+  ("octubre", "Oct"), ("oct", "Oct"), ("noviembre", "Nov"), ("nov", "Nov"),
+  ("diciembre", "Dec"), ("dic", "Dec"),
+  ("lunes", "Mon"), ("lun", "Mon"), ("martes", "Tue"),
+  ("miércoles", "Wed"), ("mié", "Wed"), ("mie", "Wed"), ("jueves", "Thu"), ("jue", "Thu"),
+  ("viernes", "Fri"), ("vie", "Fri"), ("sábado", "Sat"), ("sáb", "Sat"), ("sab", "Sat"),
+  ("domingo", "Sun"), ("dom", "Sun"),
+];
+
+const IT: &[(&str, &str)] = &[
+  ("gennaio", "Jan"), ("gen", "Jan"), ("febbraio", "Feb"), ("feb", "Feb"),
+  ("marzo", "Mar"), ("mar", "Mar"), ("aprile", "Apr"), ("apr", "Apr"), ("maggio", "May"), ("mag", "May"),
+  ("giugno", "Jun"), ("giu", "Jun"), ("luglio", "Jul"), ("lug", "Jul"), ("agosto", "Aug"), ("ago", "Aug"),
+  ("settembre", "Sep"), ("set", "Sep"), ("ottobre", "Oct"), ("ott", "Oct"),
+  ("novembre", "Nov"), ("nov", "Nov"), ("dicembre", "Dec"), ("dic", "Dec"),
+  ("lunedì", "Mon"), ("lunedi", "Mon"), ("lun", "Mon"), ("martedì", "Tue"), ("martedi", "Tue"),
+  ("mercoledì", "Wed"), ("mercoledi", "Wed"), ("mer", "Wed"),
+  ("giovedì", "Thu"), ("giovedi", "Thu"), ("gio", "Thu"),
+  ("venerdì", "Fri"), ("venerdi", "Fri"), ("ven", "Fri"),
+  ("sabato", "Sat"), ("sab", "Sat"), ("domenica", "Sun"), ("dom", "Sun"),
+];
+
+const NL: &[(&str, &str)] = &[
+  ("januari", "Jan"), ("jan", "Jan"), ("februari", "Feb"), ("feb", "Feb"),
+  ("maart", "Mar"), ("mrt", "Mar"), ("mar", "Mar"), ("april", "Apr"), ("apr", "Apr"), ("mei", "May"),
+  ("juni", "Jun"), ("jun", "Jun"), ("juli", "Jul"), ("jul", "Jul"), ("augustus", "Aug"), ("aug", "Aug"),
+  ("september", "Sep"), ("sept", "Sep"), ("sep", "Sep"), ("oktober", "Oct"), ("okt", "Oct"),
+  ("november", "Nov"), ("nov", "Nov"), ("december", "Dec"), ("dec", "Dec"),
+  ("maandag", "Mon"), ("ma", "Mon"), ("dinsdag", "Tue"), ("di", "Tue"),
+  ("woensdag", "Wed"), ("wo", "Wed"), ("donderdag", "Thu"), ("do", "Thu"),
+  ("vrijdag", "Fri"), ("vr", "Fri"), ("zaterdag", "Sat"), ("za", "Sat"),
+  ("zondag", "Sun"), ("zo", "Sun"),
+];
+
+impl Locale {
+  fn names(self) -> &'static [(&'static str, &'static str)] {
+    match self {
+      Locale::En => &[],
+      Locale::De => DE,
+      Locale::Fr => FR,
+      Locale::Es => ES,
+      Locale::It => IT,
+      Locale::Nl => NL,
+    }
+  }
+
+  /// Replaces localized month and weekday names in `datefields` with
+  /// their english abbreviations. Any punctuation (e.g. the dot in
+  /// `Okt.`) is kept, it has to be part of the time pattern.
+  pub fn to_english(self, datefields: &str) -> Cow<'_, str> {
+    let names = self.names();
+    if names.is_empty() || !datefields.chars().any(char::is_alphabetic) {
+      return Cow::Borrowed(datefields);
+    }
+
+    let mut translated = String::with_capacity(datefields.len());
+    let mut rest = datefields;
+    while let Some(start) = rest.find(char::is_alphabetic) {
+      let (before, word_and_rest) = rest.split_at(start);
+      let end = word_and_rest.find(|c: char| !c.is_alphabetic()).unwrap_or(word_and_rest.len());
+      let (word, after) = word_and_rest.split_at(end);
+
+      translated.push_str(before);
+      let lowercase = word.to_lowercase();
+      match names.iter().find(|&&(name, _)| name == lowercase) {
+        Some(&(_, english)) => translated.push_str(english),
+        None => translated.push_str(word),
+      }
+      rest = after;
+    }
+    translated.push_str(rest);
+
+    Cow::Owned(translated)
+  }
+}
+
+impl FromStr for Locale {
+  type Err = ConfigError;
+
+  fn from_str(s: &str) -> Result<Locale, ConfigError> {
+    match s.to_lowercase().as_str() {
+      "en" => Ok(Locale::En),
+      "de" => Ok(Locale::De),
+      "fr" => Ok(Locale::Fr),
+      "es" => Ok(Locale::Es),
+      "it" => Ok(Locale::It),
+      "nl" => Ok(Locale::Nl),
+      _ => Err(ConfigError::LocaleUnsupported),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use utils::parse_date;
+
+  #[test]
+  fn should_translate_localized_names() {
+    // given
+    let datefields = "Mi 13. Okt. 2018 00:03:01";
+
+    // when
+    let translated = Locale::De.to_english(datefields);
+
+    // then
+    assert_eq!(translated, "Wed 13. Oct. 2018 00:03:01");
+  }
+
+  #[test]
+  fn should_parse_localized_dates() {
+    // given
+    let pattern = "%d %b %Y %H:%M:%S";
+    let dates = [
+      (Locale::De, "13 Okt 2018 00:03:01"),
+      (Locale::Fr, "13 févr 2018 00:03:01"),
+      (Locale::Es, "13 dic 2018 00:03:01"),
+      (Locale::It, "13 giu 2018 00:03:01"),
+      (Locale::Nl, "13 mrt 2018 00:03:01"),
+    ];
+
+    for &(locale, datefields) in dates.iter() {
+      // when
+      let date = parse_date(&locale.to_english(datefields), pattern);
+
+      // then
+      assert!(date.is_some(), "{} could not be parsed", datefields);
+    }
+  }
+}
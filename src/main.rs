@@ -1,5 +1,7 @@
 extern crate check_timed_logs_fast;
 
+use check_timed_logs_fast::State;
+use std::cmp::max;
 use std::process::exit;
 
 mod args;
@@ -16,34 +18,80 @@ fn main() {
     }
   };
 
-  let res = check_timed_logs_fast::run(&conf);
+  let res = check_timed_logs_fast::run_summary(&conf);
   match res {
     Err(err) => {
       println!("ERROR: {}", err);
       exit(3);
     },
-    Ok((matches, files_matched)) => {
-      if matches >= conf.max_critical_matches {
-        println!("CRITICAL - There are {} instances of \"{}\" in the last {} minutes",
-                  matches, conf.search_pattern, conf.interval_to_check);
-        exit(2);
-      }
+    Ok(summary) => {
+      let matches = summary.matches;
+      // with percentage thresholds the number of lines is shown as well
+      let has_percentages = conf.has_percentages();
+      let percentage = match summary.match_percentage() {
+        Some(percentage) if has_percentages => format!(" ({:.2}% of {} lines)", percentage, summary.dated_lines),
+        None if has_percentages => " (no lines)".to_owned(),
+        _ => String::new(),
+      };
+      let warning_threshold = match conf.warning_percentage {
+        Some(percentage) => format!("{}%", percentage),
+        None => format!("{:?}", conf.max_warning_matches),
+      };
 
-      if matches >= conf.max_warning_matches {
-        println!("WARNING - There are {} instances of \"{}\" in the last {} minutes",
-                  matches, conf.search_pattern, conf.interval_to_check);
-        exit(1);
-      }
+      let (mut state, mut message) = if summary.reaches(conf.max_critical_matches, conf.critical_percentage) {
+        (State::Critical, format!("There are {} instances of \"{}\" in the last {} minutes{}",
+                                  matches, conf.search_pattern, conf.interval_to_check, percentage))
+      } else if summary.reaches(conf.max_warning_matches, conf.warning_percentage) {
+        (State::Warning, format!("There are {} instances of \"{}\" in the last {} minutes{}",
+                                 matches, conf.search_pattern, conf.interval_to_check, percentage))
+      } else if summary.files_matched == 0 {
+        (State::Unknown, format!("There were no files matching the passed filename: \"{}\"",
+                                 conf.logfiles.iter().chain(&conf.logdirs).cloned().collect::<Vec<_>>().join("\", \"")))
+      } else if !conf.has_match_thresholds() && conf.has_request_thresholds() {
+        // the pattern only selects the lines, so the requests are shown
+        let mut details = Vec::new();
+        if let Some(error_rate) = summary.error_rate(&conf) {
+          let classes: Vec<String> = conf.error_classes.iter().map(|class| format!("{}xx", class)).collect();
+          details.push(format!("{:.1}% had status {}", error_rate, classes.join(", ")));
+        }
+        if let Some(average) = summary.requests.average_response_time_ms() {
+          details.push(format!("average response time is {} ms", average));
+        }
+        let details = if details.is_empty() { String::new() } else { format!(" ({})", details.join(", ")) };
+        (State::Ok, format!("There are {} requests in the last {} minutes{}",
+                            summary.requests.count, conf.interval_to_check, details))
+      } else if !conf.has_match_thresholds() {
+        // the match thresholds are disabled, e.g. with only -distinct thresholds
+        (State::Ok, format!("There are {} instances of \"{}\" in the last {} minutes",
+                            matches, conf.search_pattern, conf.interval_to_check))
+      } else {
+        (State::Ok, format!("There are only {} instances of \"{}\" in the last {} minutes{} - Warning threshold is {}",
+                            matches, conf.search_pattern, conf.interval_to_check, percentage, warning_threshold))
+      };
 
-      if files_matched == 0 {
-        println!("UNKNOWN - There were no files matching the passed filename: \"{}\"",
-                  conf.logfile);
-        exit(3);
+      for (alert_state, alert) in summary.alerts(&conf) {
+        state = max(state, alert_state);
+        message.push_str(" - ");
+        message.push_str(&alert);
       }
 
-      println!("OK - There are only {} instances of \"{}\" in the last {} minutes - Warning threshold is {:?}",
-               matches, conf.search_pattern, conf.interval_to_check, conf.max_warning_matches);
-      exit(0);
+      let perfdata = summary.perfdata(&conf);
+      if perfdata.is_empty() {
+        println!("{} - {}", state, message);
+      } else {
+        println!("{} - {} | {}", state, message, perfdata);
+      }
+      if !conf.logdirs.is_empty() {
+        for (dir, matches) in summary.matches_by_directory() {
+          println!("{}: {} instances", dir, matches);
+        }
+      }
+      if conf.verbose {
+        for (minute, matches) in summary.histogram() {
+          println!("{}: {} instances", minute, matches);
+        }
+      }
+      exit(state.exit_code());
     }
   }
 }
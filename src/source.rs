@@ -0,0 +1,199 @@
+use std::io;
+use std::io::{Read, Seek, SeekFrom};
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Yields the lines of a file from the last to the first one. The newline
+/// is not part of the returned lines.
+pub trait LineSource {
+  fn prev_line(&mut self) -> io::Result<Option<&[u8]>>;
+}
+
+/// Lines of a file which is completely in memory, e.g. mapped to memory.
+pub struct SliceLines<'a> {
+  bytes: &'a [u8],
+  newline: &'static [u8],
+  /// the end of the next line
+  end: usize,
+  done: bool,
+}
+
+impl<'a> SliceLines<'a> {
+  pub fn new(bytes: &'a [u8], newline: &'static [u8]) -> SliceLines<'a> {
+    SliceLines { bytes, newline, end: bytes.len(), done: false }
+  }
+}
+
+impl<'a> LineSource for SliceLines<'a> {
+  fn prev_line(&mut self) -> io::Result<Option<&[u8]>> {
+    if self.done {
+      return Ok(None);
+    }
+
+    match find_prev_newline(&self.bytes[..self.end], 0, self.newline) {
+      Some(index) => {
+        let line = &self.bytes[index + self.newline.len()..self.end];
+        self.end = index;
+        Ok(Some(line))
+      },
+      None => {
+        self.done = true;
+        Ok(Some(&self.bytes[..self.end]))
+      },
+    }
+  }
+}
+
+/// Lines of a file which is read backwards in chunks. Used if the file
+/// can't or shouldn't be mapped to memory.
+pub struct ReverseReader<R> {
+  reader: R,
+  newline: &'static [u8],
+  chunk_size: usize,
+  /// offset in the file of the first byte in `buf`
+  pos: u64,
+  /// the bytes which were read but not returned as lines yet are `buf[..end]`
+  buf: Vec<u8>,
+  end: usize,
+  done: bool,
+}
+
+impl<R: Read + Seek> ReverseReader<R> {
+  /// `len` is the number of bytes of the file which are read.
+  pub fn new(reader: R, len: u64, newline: &'static [u8]) -> ReverseReader<R> {
+    ReverseReader::with_chunk_size(reader, len, newline, CHUNK_SIZE)
+  }
+
+  fn with_chunk_size(reader: R, len: u64, newline: &'static [u8], chunk_size: usize) -> ReverseReader<R> {
+    ReverseReader { reader, newline, chunk_size, pos: len, buf: Vec::new(), end: 0, done: false }
+  }
+
+  /// Reads the chunk before the bytes which were already read. A file which
+  /// got shorter in the meantime results in an `UnexpectedEof` error.
+  fn read_chunk(&mut self) -> io::Result<()> {
+    let size = (self.chunk_size as u64).min(self.pos) as usize;
+    self.pos -= size as u64;
+
+    let mut chunk = vec![0; size];
+    self.reader.seek(SeekFrom::Start(self.pos))?;
+    self.reader.read_exact(&mut chunk)?;
+
+    chunk.extend_from_slice(&self.buf[..self.end]);
+    self.end = chunk.len();
+    self.buf = chunk;
+    Ok(())
+  }
+}
+
+impl<R: Read + Seek> LineSource for ReverseReader<R> {
+  fn prev_line(&mut self) -> io::Result<Option<&[u8]>> {
+    if self.done {
+      return Ok(None);
+    }
+
+    loop {
+      if let Some(index) = find_prev_newline(&self.buf[..self.end], self.pos, self.newline) {
+        let end = self.end;
+        self.end = index;
+        return Ok(Some(&self.buf[index + self.newline.len()..end]));
+      }
+
+      if self.pos == 0 {
+        self.done = true;
+        return Ok(Some(&self.buf[..self.end]));
+      }
+
+      self.read_chunk()?;
+    }
+  }
+}
+
+/// Returns the index of the last newline in `bytes`. A newline only counts
+/// if its offset in the file (`offset` is the one of `bytes[0]`) is a
+/// multiple of its length, this matters for utf-16.
+fn find_prev_newline(bytes: &[u8], offset: u64, newline: &[u8]) -> Option<usize> {
+  let width = newline.len();
+  if width == 1 {
+    return bytes.iter().rposition(|&b| b == newline[0]);
+  }
+  if bytes.len() < width {
+    return None;
+  }
+
+  let mut index = bytes.len() - width;
+  loop {
+    if (offset + index as u64) % width as u64 == 0 && bytes[index..].starts_with(newline) {
+      return Some(index);
+    }
+    if index == 0 {
+      return None;
+    }
+    index -= 1;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::io::Cursor;
+
+  fn collect<S: LineSource>(source: &mut S) -> Vec<String> {
+    let mut lines = Vec::new();
+    while let Some(line) = source.prev_line().unwrap() {
+      lines.push(String::from_utf8_lossy(line).into_owned());
+    }
+    lines
+  }
+
+  #[test]
+  fn should_read_lines_backwards() {
+    // given
+    let content = b"first\nsecond line\n\nthe fourth and longest line\nlast\n";
+
+    for &chunk_size in &[1, 3, 7, CHUNK_SIZE] {
+      let mut slice = SliceLines::new(content, b"\n");
+      let mut reader = ReverseReader::with_chunk_size(Cursor::new(&content[..]), content.len() as u64, b"\n", chunk_size);
+
+      // when
+      let from_slice = collect(&mut slice);
+      let from_reader = collect(&mut reader);
+
+      // then
+      assert_eq!(from_slice, vec!["", "last", "the fourth and longest line", "", "second line", "first"]);
+      assert_eq!(from_reader, from_slice);
+    }
+  }
+
+  #[test]
+  fn should_only_split_at_aligned_newlines() {
+    // given
+    // `\u{a00}\u{100}` contains the bytes of a newline, but not at an aligned offset
+    let content: Vec<u8> = "a\u{a00}\u{100}b\nc".encode_utf16().flat_map(|u| u.to_le_bytes().to_vec()).collect();
+    let mut reader = ReverseReader::with_chunk_size(Cursor::new(&content[..]), content.len() as u64, b"\n\0", 3);
+
+    // when
+    let lines: Vec<Vec<u8>> = {
+      let mut lines = Vec::new();
+      while let Some(line) = reader.prev_line().unwrap() {
+        lines.push(line.to_vec());
+      }
+      lines
+    };
+
+    // then
+    assert_eq!(lines, vec![b"c\0".to_vec(), b"a\0\0\x0a\0\x01b\0".to_vec()]);
+  }
+
+  #[test]
+  fn should_fail_if_file_got_shorter() {
+    // given
+    let content = b"first\nsecond\n";
+    let mut reader = ReverseReader::new(Cursor::new(&content[..]), 100, b"\n");
+
+    // when
+    let res = reader.prev_line();
+
+    // then
+    assert_eq!(res.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+  }
+}
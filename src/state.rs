@@ -0,0 +1,50 @@
+use std::fmt;
+use std::str::FromStr;
+use ConfigError;
+
+/// Nagios service states. They are ordered by how much attention they
+/// need, `max` of two states returns the one which should be reported.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Copy)]
+pub enum State {
+  Ok,
+  Unknown,
+  Warning,
+  Critical,
+}
+
+impl State {
+  pub fn exit_code(self) -> i32 {
+    match self {
+      State::Ok => 0,
+      State::Warning => 1,
+      State::Critical => 2,
+      State::Unknown => 3,
+    }
+  }
+}
+
+impl fmt::Display for State {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let label = match *self {
+      State::Ok => "OK",
+      State::Warning => "WARNING",
+      State::Critical => "CRITICAL",
+      State::Unknown => "UNKNOWN",
+    };
+    write!(f, "{}", label)
+  }
+}
+
+impl FromStr for State {
+  type Err = ConfigError;
+
+  fn from_str(s: &str) -> Result<State, ConfigError> {
+    match s.to_lowercase().as_str() {
+      "ok" | "ignore" => Ok(State::Ok),
+      "warning" => Ok(State::Warning),
+      "critical" => Ok(State::Critical),
+      "unknown" => Ok(State::Unknown),
+      _ => Err(ConfigError::StateInvalid),
+    }
+  }
+}
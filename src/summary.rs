@@ -0,0 +1,343 @@
+use chrono::NaiveDateTime;
+use config::Growth;
+use distinct::Distinct;
+use Config;
+use State;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Counts for a single searched file.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FileSummary {
+  pub matches: u64,
+  /// lines with a timestamp which is not older than the interval
+  pub dated_lines: u64,
+  /// lines with a timestamp which is further in the future than the
+  /// configured tolerance
+  pub future_lines: u64,
+  /// the newest timestamp of a line, adjusted to local time
+  pub newest_ts: Option<u64>,
+  /// requests of access logs in the interval
+  pub requests: RequestStats,
+  /// matches per minute, the key is the minute of the local timestamp
+  pub match_minutes: BTreeMap<u64, u64>,
+  /// matches in the interval before the current one, only searched for
+  /// trends
+  pub previous_matches: u64,
+  /// values of the capture group which is counted distinctly
+  pub distinct: Distinct,
+  /// whether the file was truncated while it was searched
+  pub truncated: bool,
+}
+
+/// Counts of the requests in access logs.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RequestStats {
+  pub count: u64,
+  /// requests per status class, index 0 counts the 1xx responses
+  pub status_classes: [u64; 5],
+  /// sum and maximum of the response times of the requests with one
+  pub response_time_ms_total: u64,
+  pub response_time_ms_max: u64,
+  pub timed: u64,
+}
+
+impl RequestStats {
+  pub fn add_request(&mut self, status: Option<u16>, response_time_ms: Option<u64>) {
+    self.count += 1;
+    if let Some(class) = status.map(|status| (status / 100) as usize).filter(|class| (1..6).contains(class)) {
+      self.status_classes[class - 1] += 1;
+    }
+    if let Some(response_time_ms) = response_time_ms {
+      self.response_time_ms_total += response_time_ms;
+      self.response_time_ms_max = self.response_time_ms_max.max(response_time_ms);
+      self.timed += 1;
+    }
+  }
+
+  pub fn add(&mut self, other: &RequestStats) {
+    self.count += other.count;
+    for (total, count) in self.status_classes.iter_mut().zip(&other.status_classes) {
+      *total += count;
+    }
+    self.response_time_ms_total += other.response_time_ms_total;
+    self.response_time_ms_max = self.response_time_ms_max.max(other.response_time_ms_max);
+    self.timed += other.timed;
+  }
+
+  /// Number of requests with a status of class `class`, e.g. 5 for 5xx.
+  pub fn with_status_class(&self, class: usize) -> u64 {
+    self.status_classes[class - 1]
+  }
+
+  /// Average response time in milliseconds, `None` if no request had one.
+  pub fn average_response_time_ms(&self) -> Option<u64> {
+    self.response_time_ms_total.checked_div(self.timed)
+  }
+}
+
+impl FileSummary {
+  /// Counts a match in the histogram, `ts` is adjusted to local time.
+  pub fn add_match_time(&mut self, ts: u64) {
+    *self.match_minutes.entry(ts / 60).or_insert(0) += 1;
+  }
+
+  /// More than half of the recent lines have a timestamp in the future,
+  /// this usually means the timezone of the application is misconfigured.
+  pub fn is_dominated_by_future(&self) -> bool {
+    self.future_lines * 2 > self.dated_lines
+  }
+}
+
+/// The result of a check over all files.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Summary {
+  pub matches: u64,
+  pub files_matched: u64,
+  /// lines with a timestamp in the interval
+  pub dated_lines: u64,
+  pub future_lines: u64,
+  /// files in which most of the recent lines have a timestamp in the future
+  pub future_files: Vec<String>,
+  /// path and number of matches of every searched file
+  pub files: Vec<(String, u64)>,
+  /// files which were truncated while they were searched, matches in
+  /// them may be missing
+  pub truncated_files: Vec<String>,
+  /// files which could not be read and the reason why
+  pub failed_files: Vec<(String, String)>,
+  /// seconds since the most recently modified file was modified
+  pub newest_file_age: Option<u64>,
+  /// seconds since the newest timestamp of a line in any file
+  pub newest_line_age: Option<u64>,
+  /// requests of access logs in the interval
+  pub requests: RequestStats,
+  /// matches per minute of all files, see `FileSummary`
+  pub match_minutes: BTreeMap<u64, u64>,
+  /// matches in the interval before the current one, see `FileSummary`
+  pub previous_matches: u64,
+  /// values of the capture group of -distinct in all files
+  pub distinct: Distinct,
+}
+
+impl Summary {
+  pub fn add_file(&mut self, path: &str, file: &FileSummary) {
+    self.matches += file.matches;
+    self.previous_matches += file.previous_matches;
+    self.distinct.merge(&file.distinct);
+    self.dated_lines += file.dated_lines;
+    self.future_lines += file.future_lines;
+    self.requests.add(&file.requests);
+    for (&minute, &matches) in &file.match_minutes {
+      *self.match_minutes.entry(minute).or_insert(0) += matches;
+    }
+    self.files.push((path.to_owned(), file.matches));
+    if file.future_lines > 0 && file.is_dominated_by_future() {
+      self.future_files.push(path.to_owned());
+    }
+    if file.truncated {
+      self.truncated_files.push(path.to_owned());
+    }
+  }
+
+  /// Percentage of the lines in the interval which match, `None` if there
+  /// were no lines.
+  pub fn match_percentage(&self) -> Option<f64> {
+    if self.dated_lines == 0 {
+      return None;
+    }
+    Some(self.matches as f64 * 100.0 / self.dated_lines as f64)
+  }
+
+  /// Whether the matches reach the number or percentage of a threshold.
+  pub fn reaches(&self, max_matches: u64, percentage: Option<f64>) -> bool {
+    self.matches >= max_matches
+      || percentage.is_some_and(|percentage| self.match_percentage().is_some_and(|p| p >= percentage))
+  }
+
+  /// The most matches within any `minutes` consecutive minutes.
+  pub fn max_matches_within(&self, minutes: u64) -> u64 {
+    self.match_minutes.keys()
+      .map(|&start| self.match_minutes.range(start..start.saturating_add(minutes)).map(|(_, &matches)| matches).sum())
+      .max()
+      .unwrap_or(0)
+  }
+
+  /// Average number of matches per minute of the interval.
+  pub fn matches_per_minute(&self, conf: &Config) -> f64 {
+    self.matches as f64 / conf.interval_to_check.max(1) as f64
+  }
+
+  /// Matches per minute from the first to the last minute with matches,
+  /// labeled with the local time.
+  pub fn histogram(&self) -> Vec<(String, u64)> {
+    let (first, last) = match (self.match_minutes.keys().next(), self.match_minutes.keys().next_back()) {
+      (Some(&first), Some(&last)) => (first, last),
+      _ => return Vec::new(),
+    };
+    (first..=last)
+      .map(|minute| {
+        let label = NaiveDateTime::from_timestamp((minute * 60) as i64, 0).format("%Y-%m-%d %H:%M").to_string();
+        (label, self.match_minutes.get(&minute).cloned().unwrap_or(0))
+      })
+      .collect()
+  }
+
+  /// Nagios performance data of the matches, the lines and the percentage
+  /// with percentage thresholds, and of the distinct values with
+  /// -distinct. Empty without these options.
+  pub fn perfdata(&self, conf: &Config) -> String {
+    let mut perfdata = Vec::new();
+    if conf.has_percentages() {
+      let threshold = |max_matches: u64| if max_matches == u64::MAX { String::new() } else { max_matches.to_string() };
+      let percentage = |percentage: Option<f64>| percentage.map(|p| p.to_string()).unwrap_or_default();
+      perfdata.push(format!("matches={};{};{};0 lines={};;;0 ratio={:.2}%;{};{};0;100",
+              self.matches, threshold(conf.max_warning_matches), threshold(conf.max_critical_matches),
+              self.dated_lines, self.match_percentage().unwrap_or(0.0),
+              percentage(conf.warning_percentage), percentage(conf.critical_percentage)));
+    }
+    if conf.distinct_group.is_some() {
+      let threshold = |value: Option<u64>| value.map(|v| v.to_string()).unwrap_or_default();
+      perfdata.push(format!("distinct={};{};{};0", self.distinct.count(),
+                            threshold(conf.distinct_warning), threshold(conf.distinct_critical)));
+    }
+    perfdata.join(" ")
+  }
+
+  /// Percentage of the requests with one of the error status classes,
+  /// `None` if there were no requests.
+  pub fn error_rate(&self, conf: &Config) -> Option<f64> {
+    if self.requests.count == 0 {
+      return None;
+    }
+    let errors: u64 = conf.error_classes.iter().map(|&class| self.requests.with_status_class(class)).sum();
+    Some(errors as f64 * 100.0 / self.requests.count as f64)
+  }
+
+  /// Number of matches per directory which contains searched files.
+  pub fn matches_by_directory(&self) -> BTreeMap<String, u64> {
+    let mut directories = BTreeMap::new();
+    for &(ref path, matches) in &self.files {
+      let dir = Path::new(path).parent().map(|d| d.display().to_string()).unwrap_or_default();
+      *directories.entry(dir).or_insert(0) += matches;
+    }
+    directories
+  }
+
+  /// Problems which are found besides the number of matches. Each one comes
+  /// with the state it should raise.
+  pub fn alerts(&self, conf: &Config) -> Vec<(State, String)> {
+    let mut alerts = Vec::new();
+
+    if !self.future_files.is_empty() && conf.future_state != State::Ok {
+      alerts.push((conf.future_state,
+                   format!("{} lines have a timestamp in the future, check the timezone of: {}",
+                           self.future_lines, self.future_files.join(", "))));
+    }
+
+    if !self.truncated_files.is_empty() && conf.truncated_state != State::Ok {
+      alerts.push((conf.truncated_state,
+                   format!("files were truncated during the search, matches may be missing: {}",
+                           self.truncated_files.join(", "))));
+    }
+
+    if !self.failed_files.is_empty() && conf.io_error_state != State::Ok {
+      let failed: Vec<String> = self.failed_files.iter()
+        .map(|(path, reason)| format!("{} ({})", path, reason))
+        .collect();
+      alerts.push((conf.io_error_state, format!("files can not be read: {}", failed.join(", "))));
+    }
+
+    if let Some(alert) = self.stale_alert(conf) {
+      alerts.push(alert);
+    }
+
+    let burst = self.max_matches_within(conf.burst_minutes);
+    let thresholds = (conf.burst_warning.map(|m| m as f64), conf.burst_critical.map(|m| m as f64));
+    if let Some(state) = threshold_state(burst as f64, thresholds.0, thresholds.1) {
+      alerts.push((state, format!("{} matches within {} minutes", burst, conf.burst_minutes)));
+    }
+
+    if conf.distinct_group.is_some() {
+      let distinct = self.distinct.count();
+      let state = if conf.distinct_critical.is_some_and(|critical| distinct >= critical) {
+        Some(State::Critical)
+      } else if conf.distinct_warning.is_some_and(|warning| distinct >= warning) {
+        Some(State::Warning)
+      } else {
+        None
+      };
+      if let Some(state) = state {
+        alerts.push((state, format!("{} distinct values of {}", distinct, conf.distinct_name)));
+      }
+    }
+
+    if let Some(state) = self.trend_state(conf) {
+      alerts.push((state, format!("{} matches, {} in the previous {} minutes",
+                                  self.matches, self.previous_matches, conf.interval_to_check)));
+    }
+
+    let rate = self.matches_per_minute(conf);
+    if let Some(state) = threshold_state(rate, conf.rate_warning, conf.rate_critical) {
+      alerts.push((state, format!("{:.2} matches per minute", rate)));
+    }
+
+    if let Some(error_rate) = self.error_rate(conf) {
+      if let Some(state) = threshold_state(error_rate, conf.error_rate_warning, conf.error_rate_critical) {
+        let classes: Vec<String> = conf.error_classes.iter().map(|class| format!("{}xx", class)).collect();
+        alerts.push((state, format!("{:.1}% of {} requests had status {}", error_rate, self.requests.count, classes.join(", "))));
+      }
+    }
+
+    if let Some(average) = self.requests.average_response_time_ms() {
+      let thresholds = (conf.latency_warning.map(|ms| ms as f64), conf.latency_critical.map(|ms| ms as f64));
+      if let Some(state) = threshold_state(average as f64, thresholds.0, thresholds.1) {
+        alerts.push((state, format!("average response time is {} ms (max {} ms)", average, self.requests.response_time_ms_max)));
+      }
+    }
+
+    alerts
+  }
+
+  /// The state if the matches grew compared to the previous interval.
+  fn trend_state(&self, conf: &Config) -> Option<State> {
+    let exceeds = |growth: &Option<Growth>| growth.as_ref()
+      .is_some_and(|growth| growth.is_exceeded(self.previous_matches, self.matches));
+    if exceeds(&conf.trend_critical) {
+      Some(State::Critical)
+    } else if exceeds(&conf.trend_warning) {
+      Some(State::Warning)
+    } else {
+      None
+    }
+  }
+
+  /// The log is stale if the newest file wasn't modified or the newest line
+  /// was not written within the configured number of minutes.
+  fn stale_alert(&self, conf: &Config) -> Option<(State, String)> {
+    if conf.stale_warning.is_none() && conf.stale_critical.is_none() {
+      return None;
+    }
+
+    let age = match (self.newest_file_age, self.newest_line_age) {
+      (None, _) => return Some((conf.stale_critical.map_or(State::Warning, |_| State::Critical),
+                                "no log file was found".to_owned())),
+      (Some(file_age), line_age) => line_age.map_or(file_age, |line_age| line_age.max(file_age)),
+    };
+
+    let to_secs = |minutes: u64| (minutes * 60) as f64;
+    let state = threshold_state(age as f64, conf.stale_warning.map(to_secs), conf.stale_critical.map(to_secs))?;
+    Some((state, format!("log is stale, nothing was written for {} minutes", age / 60)))
+  }
+}
+
+/// The state if `value` exceeds one of the thresholds.
+fn threshold_state(value: f64, warning: Option<f64>, critical: Option<f64>) -> Option<State> {
+  if critical.is_some_and(|critical| value > critical) {
+    Some(State::Critical)
+  } else if warning.is_some_and(|warning| value > warning) {
+    Some(State::Warning)
+  } else {
+    None
+  }
+}
@@ -4,9 +4,22 @@ extern crate std;
 use Config;
 use chrono::prelude::*;
 use std::fs;
+use std::io;
 use std::str;
 use std::time::UNIX_EPOCH;
 
+/// Whether the file is a named pipe.
+#[cfg(unix)]
+pub fn is_fifo(metadata: &fs::Metadata) -> bool {
+  use std::os::unix::fs::FileTypeExt;
+  metadata.file_type().is_fifo()
+}
+
+#[cfg(not(unix))]
+pub fn is_fifo(_metadata: &fs::Metadata) -> bool {
+  false
+}
+
 pub fn get_oldest_allowed_utc_ts(conf: &Config, now: std::time::SystemTime) -> u64 {
   let since_the_epoch = now.duration_since(UNIX_EPOCH).expect("Time went backwards");
   let now_unix_ts = since_the_epoch.as_secs();
@@ -26,23 +39,39 @@ pub fn get_oldest_allowed_local_ts(conf: &Config, now: std::time::SystemTime) ->
   get_timestamp_from_local(adjusted_date)
 }
 
-/// check if the file age is >= now - interval_to_check
-pub fn check_file_age(conf: &Config, path: &str) -> bool {
-  let secs_allowed = conf.interval_to_check * 60;
+/// lines with a timestamp newer than this are considered to be in the future
+pub fn get_newest_allowed_local_ts(conf: &Config, now: std::time::SystemTime) -> u64 {
+  let since_the_epoch = now.duration_since(UNIX_EPOCH).expect("Time went backwards");
+  let newest_ts_utc = since_the_epoch.as_secs() + conf.future_tolerance;
+  let newest_date_no_tz_offset = NaiveDateTime::from_timestamp(newest_ts_utc as i64, 0);
+  let adjusted_date = adjust_to_local_tz(newest_date_no_tz_offset);
+  get_timestamp_from_local(adjusted_date)
+}
+
+/// now adjusted to local time, on the same scale as the timestamps of lines
+pub fn get_now_local_ts(now: std::time::SystemTime) -> u64 {
+  let since_the_epoch = now.duration_since(UNIX_EPOCH).expect("Time went backwards");
+  get_local_ts_from_utc(since_the_epoch.as_secs() as i64)
+}
+
+/// converts a unix timestamp to the scale of the timestamps of lines
+pub fn get_local_ts_from_utc(utc_ts: i64) -> u64 {
+  let date_no_tz_offset = NaiveDateTime::from_timestamp(utc_ts, 0);
+  get_timestamp_from_local(adjust_to_local_tz(date_no_tz_offset))
+}
 
-  let attr = fs::metadata(&path).expect("cannot get metadata");
-  let last_modified = attr.modified().unwrap();
-  let elapsed_secs = last_modified.elapsed().unwrap().as_secs();
+/// seconds since the file was modified
+pub fn get_file_age(conf: &Config, path: &str) -> io::Result<u64> {
+  let attr = fs::metadata(path)?;
+  let last_modified = attr.modified()?;
+  // a modification time in the future counts as just modified
+  let elapsed_secs = last_modified.elapsed().map(|e| e.as_secs()).unwrap_or(0);
 
   if conf.debug {
     println!("found file {} is {} seconds old", path, elapsed_secs);
   }
 
-  if elapsed_secs <= secs_allowed {
-    return true;
-  }
-
-  false
+  Ok(elapsed_secs)
 }
 
 pub fn adjust_to_local_tz(date: NaiveDateTime) -> DateTime<chrono::Local> {
@@ -64,7 +93,8 @@ pub fn parse_date(datefields: &str, pattern: &str) -> Option<DateTime<Utc>> {
         // the original check_timed_logs.pl would just ignore the trailing input,
         // but unfortunately chrono does not support ignoring trailing input.
         // hence this hack.
-        let comma_pos = datefields.find(',').unwrap_or(datefields.len());
+        // without a comma there is nothing to cut off, the line is ignored.
+        let comma_pos = datefields.find(',')?;
         let (before_comma, _) = datefields.split_at(comma_pos);
         return parse_date(&before_comma, pattern);
       }
@@ -103,6 +133,20 @@ pub fn get_timestamp(date: DateTime<chrono::Utc>) -> u64 {
 mod tests {
   use super::*;
 
+  #[test]
+  fn should_ignore_trailing_input_after_a_comma() {
+    // given
+    let pattern = "%Y-%m-%d %H:%M:%S";
+
+    // when
+    let with_comma = parse_date("2018-09-13 00:03:01,123", pattern);
+    let without_comma = parse_date("2018-09-13 00:03:01.123", pattern);
+
+    // then
+    assert_eq!(with_comma.unwrap().timestamp(), 1536796981);
+    assert_eq!(without_comma, None);
+  }
+
   #[test]
   fn should_prepend_current_year() {
     // given